@@ -0,0 +1,142 @@
+//! # Alarm
+//!
+//! An alarm that mimics iOS's alarm.
+//!
+//! ## Usage
+//!
+//! - Use `Alarm::new(<time>)` to initialise a new alarm that rings at `<time>` (a
+//!   `chrono::NaiveTime`) of the local time zone. The alarm is enabled and rings once, at the
+//!   first occurrence of `<time>` from now.
+//! - Set `.repeat` to the weekdays on which the alarm should ring every week, and `.label`,
+//!   `.snooze` and `.max_snoozes` as you like.
+//! - Call `.next_firing()` for the moment at which the alarm will ring, and `.is_due()` to tell
+//!   whether it is ringing.
+//! - While ringing, call `.snooze()` to ring again after `.snooze`, or `.dismiss()` to stop it
+//!   until its next occurrence. A one-off alarm is disabled upon dismissal.
+
+use crate::clock::{resolve_local, ClockSource, SystemClock};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Weekday};
+
+/// An alarm
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alarm<C = SystemClock> {
+    pub time: NaiveTime, // time of day at which the alarm rings
+    pub label: String,
+    pub enabled: bool,
+    pub repeat: Vec<Weekday>, // weekdays on which the alarm rings; rings once if empty
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub snooze: Duration, // snooze length
+    pub max_snoozes: Option<u32>, // maximum number of consecutive snoozes; unlimited if `None`
+    pub snoozes: u32,         // number of snoozes since the alarm last rang
+    pub snoozed_until: Option<DateTime<Local>>,
+    pub armed_since: DateTime<Local>, // the alarm rings at the first occurrence of `time` after this
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: C,
+}
+
+impl Alarm {
+    /// Returns an enabled one-off alarm that rings at the next `time`
+    pub fn new(time: NaiveTime) -> Self {
+        Self::with_clock(time, SystemClock)
+    }
+}
+
+impl<C: ClockSource> Alarm<C> {
+    /// `Alarm::new(time)` on another [clock source](../clock/index.html)
+    pub fn with_clock(time: NaiveTime, clock: C) -> Self {
+        Self {
+            time,
+            label: String::new(),
+            enabled: true,
+            repeat: Vec::new(),
+            snooze: Duration::minutes(9),
+            max_snoozes: None,
+            snoozes: 0,
+            snoozed_until: None,
+            armed_since: clock.now(),
+            clock,
+        }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// The moment at which the alarm will ring (or started ringing, if it is due).
+    /// `None` if the alarm is disabled.
+    pub fn next_firing(&self) -> Option<DateTime<Local>> {
+        if !self.enabled {
+            return None;
+        }
+        self.snoozed_until
+            .or_else(|| self.next_occurrence_after(self.armed_since))
+    }
+    /// The first occurrence of `.time` on one of the `.repeat` days (or on any day, if the
+    /// alarm does not repeat) strictly after `moment`, regardless of whether the alarm is enabled
+    pub fn next_occurrence_after(&self, moment: DateTime<Local>) -> Option<DateTime<Local>> {
+        let date = moment.date_naive();
+        (0..=7)
+            .map(|days| date + Duration::days(days))
+            .filter(|date| self.repeat.is_empty() || self.repeat.contains(&date.weekday()))
+            .filter_map(|date| resolve_local(date.and_time(self.time)))
+            .find(|occurrence| *occurrence > moment)
+    }
+    /// Whether the alarm is ringing
+    pub fn is_due(&self) -> bool {
+        self.is_due_at(self.clock.now())
+    }
+
+    pub fn is_due_at(&self, moment: DateTime<Local>) -> bool {
+        self.next_firing().is_some_and(|firing| firing <= moment)
+    }
+    /// Snooze the alarm. If the alarm is ringing and may be snoozed again, return
+    /// `Some(<moment at which it rings again>)`. Otherwise, return `None`.
+    pub fn snooze(&mut self) -> Option<DateTime<Local>> {
+        self.snooze_at(self.clock.now())
+    }
+
+    pub fn snooze_at(&mut self, moment: DateTime<Local>) -> Option<DateTime<Local>> {
+        if !self.is_due_at(moment) || self.max_snoozes.is_some_and(|max| self.snoozes >= max) {
+            return None;
+        }
+        let until = moment + self.snooze;
+        self.snoozes += 1;
+        self.snoozed_until = Some(until);
+        Some(until)
+    }
+    /// Stop the alarm until its next occurrence, cancelling any pending snooze. A one-off alarm
+    /// is disabled. Returns `false` (and does nothing) if the alarm is neither ringing nor snoozed.
+    pub fn dismiss(&mut self) -> bool {
+        self.dismiss_at(self.clock.now())
+    }
+
+    pub fn dismiss_at(&mut self, moment: DateTime<Local>) -> bool {
+        if self.snoozed_until.is_none() && !self.is_due_at(moment) {
+            return false;
+        }
+        self.armed_since = moment;
+        self.snoozes = 0;
+        self.snoozed_until = None;
+        if self.repeat.is_empty() {
+            self.enabled = false;
+        }
+        true
+    }
+    /// Enable the alarm. It will ring at the first occurrence of `.time` from now.
+    pub fn enable(&mut self) {
+        self.enable_at(self.clock.now());
+    }
+
+    pub fn enable_at(&mut self, moment: DateTime<Local>) {
+        self.enabled = true;
+        self.armed_since = moment;
+        self.snoozes = 0;
+        self.snoozed_until = None;
+    }
+    /// Disable the alarm, cancelling any pending snooze.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.snoozes = 0;
+        self.snoozed_until = None;
+    }
+}
@@ -0,0 +1,221 @@
+//! # Calendar
+//!
+//! Time spent, totalled by hour, day, week or month in the local time zone. A running segment
+//! that crosses a boundary (e.g. midnight) counts towards both sides of it, rather than all
+//! towards the day it started.
+//!
+//! ## Usage
+//!
+//! - Use `Calendar::default()` for weeks starting on Monday (as in ISO 8601) and days starting
+//!   at midnight, or set `.week_start` and `.rollover_hour` (e.g. to 4 for a day that runs until
+//!   4 am).
+//! - Call `.totals(<period>, <segments>)` with the running segments of your sessions, e.g.
+//!   `data.running_segments()` of a [`StopwatchData`](../stopwatch/struct.StopwatchData.html)
+//!   or [`TimerData`](../timer/struct.TimerData.html). It returns the time spent in each
+//!   [`Period`](enum.Period.html), keyed by the moment the period starts.
+
+use crate::clock::resolve_local;
+use crate::segment::Span;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Weekday,
+};
+use std::collections::BTreeMap;
+
+/// A period of the calendar to total time by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Period {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+/// Where days and weeks begin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calendar {
+    pub week_start: Weekday,
+    pub rollover_hour: u32, // the hour (0 to 23) at which a day begins
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            week_start: Weekday::Mon,
+            rollover_hour: 0,
+        }
+    }
+}
+
+impl Calendar {
+    /// The time spent in `segments` in each `period`, keyed by the moment the period starts.
+    /// Periods with no time spent are left out.
+    pub fn totals(
+        &self,
+        period: Period,
+        segments: impl IntoIterator<Item = Span>,
+    ) -> BTreeMap<DateTime<Local>, Duration> {
+        let mut totals = BTreeMap::new();
+        for segment in segments {
+            let mut moment = segment.start;
+            while moment < segment.end {
+                let (start, end) = self.bounds(period, moment);
+                // a boundary not after `moment` could only come from a time zone oddity
+                let end = if end > moment {
+                    end.min(segment.end)
+                } else {
+                    segment.end
+                };
+                *totals.entry(start).or_insert_with(Duration::zero) += end - moment;
+                moment = end;
+            }
+        }
+        totals
+    }
+    /// The start and end of the `period` containing `moment`
+    pub fn bounds(
+        &self,
+        period: Period,
+        moment: DateTime<Local>,
+    ) -> (DateTime<Local>, DateTime<Local>) {
+        if period == Period::Hour {
+            // counted from the moment itself, so that an hour repeated when clocks go back is
+            // still two hours
+            let start = moment
+                - Duration::minutes(i64::from(moment.minute()))
+                - Duration::seconds(i64::from(moment.second()))
+                - Duration::nanoseconds(i64::from(moment.nanosecond()));
+            return (start, start + Duration::hours(1));
+        }
+        let rollover = self.rollover_hour.min(23);
+        let date = (moment.naive_local() - Duration::hours(i64::from(rollover))).date();
+        let (start, end) = match period {
+            Period::Week => {
+                let days = (7 + date.weekday().num_days_from_monday()
+                    - self.week_start.num_days_from_monday())
+                    % 7;
+                let start = date - Duration::days(i64::from(days));
+                (start, start + Duration::days(7))
+            }
+            Period::Month => {
+                let start = first_of_month(date.year(), date.month());
+                let end = if date.month() == 12 {
+                    first_of_month(date.year() + 1, 1)
+                } else {
+                    first_of_month(date.year(), date.month() + 1)
+                };
+                (start, end)
+            }
+            _ => (date, date + Duration::days(1)),
+        };
+        let at_rollover = |date: NaiveDate| local(date.and_hms_opt(rollover, 0, 0).unwrap());
+        (at_rollover(start), at_rollover(end))
+    }
+}
+
+fn first_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+}
+
+/// The moment of a local date and time, even if it was skipped over
+fn local(datetime: NaiveDateTime) -> DateTime<Local> {
+    resolve_local(datetime).unwrap_or_else(|| Local.from_utc_datetime(&datetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // mid-June, away from the daylight saving transitions of most time zones
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn totals(
+        calendar: Calendar,
+        period: Period,
+        segments: &[Span],
+    ) -> Vec<(DateTime<Local>, Duration)> {
+        calendar
+            .totals(period, segments.iter().copied())
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn days_across_midnight() {
+        let segments = [Span::new(at(6, 10, 22, 0), at(6, 11, 2, 0))];
+        assert_eq!(
+            totals(Calendar::default(), Period::Day, &segments),
+            vec![
+                (at(6, 10, 0, 0), Duration::hours(2)),
+                (at(6, 11, 0, 0), Duration::hours(2)),
+            ]
+        );
+        let calendar = Calendar {
+            rollover_hour: 4,
+            ..Calendar::default()
+        };
+        assert_eq!(
+            totals(calendar, Period::Day, &segments),
+            vec![(at(6, 10, 4, 0), Duration::hours(4))]
+        );
+    }
+
+    #[test]
+    fn hours() {
+        let segments = [
+            Span::new(at(6, 10, 10, 30), at(6, 10, 12, 15)),
+            Span::new(at(6, 10, 12, 45), at(6, 10, 13, 0)),
+            Span::new(at(6, 10, 15, 0), at(6, 10, 15, 10)),
+        ];
+        assert_eq!(
+            totals(Calendar::default(), Period::Hour, &segments),
+            vec![
+                (at(6, 10, 10, 0), Duration::minutes(30)),
+                (at(6, 10, 11, 0), Duration::hours(1)),
+                (at(6, 10, 12, 0), Duration::minutes(30)),
+                (at(6, 10, 15, 0), Duration::minutes(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn weeks_and_months() {
+        // Sunday night into Monday
+        let segments = [Span::new(at(6, 16, 23, 0), at(6, 17, 1, 0))];
+        assert_eq!(
+            totals(Calendar::default(), Period::Week, &segments),
+            vec![
+                (at(6, 10, 0, 0), Duration::hours(1)),
+                (at(6, 17, 0, 0), Duration::hours(1)),
+            ]
+        );
+        let calendar = Calendar {
+            week_start: Weekday::Sun,
+            ..Calendar::default()
+        };
+        assert_eq!(
+            totals(calendar, Period::Week, &segments),
+            vec![(at(6, 16, 0, 0), Duration::hours(2))]
+        );
+        let segments = [Span::new(at(6, 30, 23, 0), at(7, 1, 1, 0))];
+        assert_eq!(
+            totals(Calendar::default(), Period::Month, &segments),
+            vec![
+                (at(6, 1, 0, 0), Duration::hours(1)),
+                (at(7, 1, 0, 0), Duration::hours(1)),
+            ]
+        );
+        assert_eq!(
+            Calendar::default().bounds(Period::Month, at(12, 31, 12, 0)),
+            (
+                at(12, 1, 0, 0),
+                Local.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+            )
+        );
+    }
+}
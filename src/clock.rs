@@ -0,0 +1,202 @@
+//! # Clock sources
+//!
+//! Where [`Stopwatch`](../stopwatch/struct.Stopwatch.html) and [`Timer`](../timer/struct.Timer.html)
+//! get the current moment from.
+//!
+//! ## Usage
+//!
+//! - Every type that reads the current moment (`Stopwatch`, `Timer`, `Alarm`, `Countdown`,
+//!   `Pomodoro`, `Sequence`, `TimerManager`, `WorldClock` and the shared handles) uses the
+//!   system clock when created with `::new(...)`, and has a `::with_clock(..., <clock>)`
+//!   constructor taking any [`ClockSource`](trait.ClockSource.html) instead.
+//! - [`SystemClock`](struct.SystemClock.html) reads the system's local time and is the default.
+//! - [`ManualClock`](struct.ManualClock.html) only moves when you `.set()` or `.advance()` it,
+//!   which makes every operation (including `.read()`) deterministic under test. Clones share
+//!   the same time, so keep one clone to drive the clock handed to a stopwatch or timer.
+//!   `.set()` only moves the wall clock, simulating an NTP correction or a manual clock change.
+//! - [`OffsetClock`](struct.OffsetClock.html) shifts another clock by a fixed duration.
+//!
+//! ## Examples
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use clock_core::clock::ManualClock;
+//! use clock_core::stopwatch::Stopwatch;
+//!
+//! let clock = ManualClock::new(Local::now());
+//! let mut stopwatch = Stopwatch::with_clock(clock.clone());
+//! stopwatch.resume();
+//! clock.advance(Duration::seconds(5));
+//! assert_eq!(stopwatch.read(), Duration::seconds(5));
+//! ```
+//!
+//! ## Monotonic time
+//!
+//! Besides the wall-clock moment, a clock source provides a monotonic [`Instant`]. While running,
+//! `Stopwatch` and `Timer` measure elapsed time on the monotonic clock, so wall-clock jumps do
+//! not make them jump or go negative. Wall-clock moments are still recorded for display.
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// A source of the current moment.
+pub trait ClockSource {
+    /// The current moment
+    fn now(&self) -> DateTime<Local>;
+    /// The current monotonic instant, which never goes backwards
+    fn instant(&self) -> Instant;
+}
+
+impl<C: ClockSource + ?Sized> ClockSource for &C {
+    fn now(&self) -> DateTime<Local> {
+        (**self).now()
+    }
+    fn instant(&self) -> Instant {
+        (**self).instant()
+    }
+}
+
+/// The system's local time. This is the default clock source.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+struct ManualClockState {
+    now: DateTime<Local>,
+    instant: Instant,
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualClockState>>,
+}
+
+impl ManualClock {
+    /// Initialise a manual clock at `moment`.
+    pub fn new(moment: DateTime<Local>) -> Self {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        Self {
+            state: Arc::new(Mutex::new(ManualClockState {
+                now: moment,
+                instant: *ORIGIN.get_or_init(Instant::now),
+            })),
+        }
+    }
+    /// Set the wall clock to `moment`. The monotonic clock is unaffected.
+    pub fn set(&self, moment: DateTime<Local>) {
+        self.state.lock().unwrap().now = moment;
+    }
+    /// Move the clock forward by `duration`. A negative `duration` only moves the wall clock
+    /// backward; the monotonic clock never goes backwards.
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now += duration;
+        if let Ok(duration) = duration.to_std() {
+            state.instant += duration;
+        }
+    }
+}
+
+impl ClockSource for ManualClock {
+    fn now(&self) -> DateTime<Local> {
+        self.state.lock().unwrap().now
+    }
+    fn instant(&self) -> Instant {
+        self.state.lock().unwrap().instant
+    }
+}
+
+/// Another clock shifted by a fixed offset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OffsetClock<C = SystemClock> {
+    pub inner: C,
+    pub offset: Duration,
+}
+
+impl<C: ClockSource> OffsetClock<C> {
+    /// Wrap `inner`, reporting moments `offset` later than it does.
+    pub fn new(inner: C, offset: Duration) -> Self {
+        Self { inner, offset }
+    }
+}
+
+impl<C: ClockSource> ClockSource for OffsetClock<C> {
+    fn now(&self) -> DateTime<Local> {
+        self.inner.now() + self.offset
+    }
+    fn instant(&self) -> Instant {
+        self.inner.instant()
+    }
+}
+
+/// Pairs a wall-clock moment with the monotonic instant it was read at, so that later moments
+/// can be derived from the monotonic clock alone.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Anchor {
+    moment: DateTime<Local>,
+    instant: Instant,
+}
+
+impl Anchor {
+    /// Anchor `moment` to the current monotonic instant of `clock`
+    pub(crate) fn new<C: ClockSource>(moment: DateTime<Local>, clock: &C) -> Self {
+        Self {
+            moment,
+            instant: clock.instant(),
+        }
+    }
+    /// The anchored moment plus the monotonic time elapsed since
+    pub(crate) fn now<C: ClockSource>(&self, clock: &C) -> DateTime<Local> {
+        self.moment + self.elapsed(clock)
+    }
+    /// The monotonic time elapsed since the anchored moment
+    pub(crate) fn elapsed<C: ClockSource>(&self, clock: &C) -> Duration {
+        let elapsed = clock.instant().saturating_duration_since(self.instant);
+        Duration::from_std(elapsed).unwrap()
+    }
+    pub(crate) fn moment(&self) -> DateTime<Local> {
+        self.moment
+    }
+    /// `moment` anchored to the instant it corresponds to on the same monotonic clock
+    pub(crate) fn shifted(&self, moment: DateTime<Local>) -> Self {
+        let offset = moment - self.moment;
+        let instant = match offset.to_std() {
+            Ok(offset) => self.instant + offset,
+            Err(_) => (-offset)
+                .to_std()
+                .ok()
+                .and_then(|offset| self.instant.checked_sub(offset))
+                .unwrap_or(self.instant),
+        };
+        Self { moment, instant }
+    }
+}
+
+pub(crate) const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// The length of `duration` in nanoseconds, without the overflow of `num_nanoseconds()`
+pub(crate) fn as_nanos(duration: Duration) -> i128 {
+    i128::from(duration.num_seconds()) * NANOS_PER_SEC + i128::from(duration.subsec_nanos())
+}
+
+/// The moment of a local date and time. A time that occurs twice (when clocks go back) resolves
+/// to the earlier moment; a time skipped over (when clocks go forward) resolves to the same
+/// wall-clock time an hour later, i.e. just after the transition.
+pub(crate) fn resolve_local(datetime: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&datetime).earliest().or_else(|| {
+        Local
+            .from_local_datetime(&(datetime + Duration::hours(1)))
+            .earliest()
+    })
+}
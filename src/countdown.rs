@@ -0,0 +1,110 @@
+//! # Countdown
+//!
+//! A countdown to a fixed moment (e.g. New Year, or a release cutoff). Unlike
+//! [`Timer`](../timer/struct.Timer.html), it runs from the moment it is created and cannot be
+//! paused.
+//!
+//! ## Usage
+//!
+//! - Use `Countdown::new(<deadline>)` to initialise a new countdown to `<deadline>`, a
+//!   `DateTime<Local>`.
+//! - Call `.read()` for the time remaining, or `.breakdown()` for it broken into days, hours,
+//!   minutes and seconds. Both stay at zero once the deadline has passed.
+//!
+//! ## Daylight saving time
+//!
+//! `.read()` is the exact time remaining, so it is an hour shorter or longer than the wall-clock
+//! difference when a DST change falls before the deadline. `.breakdown()` counts calendar days
+//! instead: from 12:00 the day before clocks go forward to 12:00 the day after is 2 days, not
+//! 1 day and 23 hours. The hours, minutes and seconds after the last whole day are exact, and
+//! 24 hours or more of them (when clocks go back in between) count as a further day.
+
+use crate::clock::{resolve_local, ClockSource, SystemClock};
+use chrono::{DateTime, Duration, Local};
+
+/// The time remaining in a [`Countdown`](struct.Countdown.html), broken into units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Breakdown {
+    pub days: i64, // calendar days
+    pub hours: i64,
+    pub minutes: i64,
+    pub seconds: i64,
+    pub nanoseconds: i64,
+}
+
+/// A countdown to a fixed moment
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Countdown<C = SystemClock> {
+    pub deadline: DateTime<Local>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: C,
+}
+
+impl Countdown {
+    /// Returns a countdown to `deadline`
+    pub fn new(deadline: DateTime<Local>) -> Self {
+        Self::with_clock(deadline, SystemClock)
+    }
+}
+
+impl<C: ClockSource> Countdown<C> {
+    /// `Countdown::new(deadline)` on another [clock source](../clock/index.html)
+    pub fn with_clock(deadline: DateTime<Local>, clock: C) -> Self {
+        Self { deadline, clock }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// Read the time remaining
+    pub fn read(&self) -> Duration {
+        self.read_at(self.clock.now())
+    }
+
+    pub fn read_at(&self, moment: DateTime<Local>) -> Duration {
+        (self.deadline - moment).max(Duration::zero())
+    }
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(self.clock.now())
+    }
+
+    pub fn is_expired_at(&self, moment: DateTime<Local>) -> bool {
+        moment >= self.deadline
+    }
+    /// Read the time remaining, broken into calendar days, hours, minutes and seconds
+    pub fn breakdown(&self) -> Breakdown {
+        self.breakdown_at(self.clock.now())
+    }
+
+    pub fn breakdown_at(&self, moment: DateTime<Local>) -> Breakdown {
+        if self.is_expired_at(moment) {
+            return Breakdown::default();
+        }
+        // whole calendar days: the same wall-clock time `days` days later is not past the deadline
+        let mut days = (self.deadline.naive_local() - moment.naive_local()).num_days();
+        let mut day_boundary = moment;
+        while days > 0 {
+            match resolve_local(moment.naive_local() + Duration::days(days)) {
+                Some(boundary) if boundary <= self.deadline => {
+                    day_boundary = boundary;
+                    break;
+                }
+                _ => days -= 1,
+            }
+        }
+        let mut rest = self.deadline - day_boundary;
+        if rest >= Duration::days(1) {
+            days += rest.num_days();
+            rest = rest - Duration::days(rest.num_days());
+        }
+        Breakdown {
+            days,
+            hours: rest.num_hours(),
+            minutes: rest.num_minutes() % 60,
+            seconds: rest.num_seconds() % 60,
+            nanoseconds: i64::from(rest.subsec_nanos()),
+        }
+    }
+}
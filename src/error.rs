@@ -0,0 +1,60 @@
+//! # Errors
+//!
+//! The error returned by the `try_` methods of [`Stopwatch`](../stopwatch/struct.Stopwatch.html)
+//! and [`Timer`](../timer/struct.Timer.html) when an operation is not valid in the current state,
+//! or by `.validate()` when their recorded data is inconsistent.
+
+use chrono::{DateTime, Local};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockError {
+    /// The operation requires a running clock, but it is paused (e.g. pausing twice)
+    NotRunning,
+    /// The operation requires a paused clock, but it is running (e.g. resuming twice)
+    AlreadyRunning,
+    /// The clock has never been started
+    NotStarted,
+    /// `moment` is earlier than `last`, the moment of the last recorded event
+    OutOfOrder {
+        moment: DateTime<Local>,
+        last: DateTime<Local>,
+    },
+    /// The start and pause moments do not alternate start, pause, start, ... in order. `index`
+    /// counts both, from the first start.
+    NotInterleaved { index: usize },
+    /// The clock was not running at `moment`, e.g. a lap recorded during a pause
+    NotRunningAt { moment: DateTime<Local> },
+    /// There is no recorded start, pause or lap at `index`
+    NoSuchEvent { index: usize },
+    /// The only lap cannot be deleted, as there is no other lap to merge it into
+    OnlyLap,
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockError::NotRunning => write!(f, "not running"),
+            ClockError::AlreadyRunning => write!(f, "already running"),
+            ClockError::NotStarted => write!(f, "never started"),
+            ClockError::OutOfOrder { moment, last } => write!(
+                f,
+                "moment {} is earlier than the last recorded event at {}",
+                moment.to_rfc3339(),
+                last.to_rfc3339()
+            ),
+            ClockError::NotInterleaved { index } => write!(
+                f,
+                "start and pause moments out of order at moment {}",
+                index
+            ),
+            ClockError::NotRunningAt { moment } => {
+                write!(f, "not running at {}", moment.to_rfc3339())
+            }
+            ClockError::NoSuchEvent { index } => write!(f, "no recorded event at index {}", index),
+            ClockError::OnlyLap => write!(f, "cannot delete the only lap"),
+        }
+    }
+}
+
+impl Error for ClockError {}
@@ -0,0 +1,88 @@
+//! # Events
+//!
+//! State changes of [`Stopwatch`](../stopwatch/struct.Stopwatch.html) and
+//! [`Timer`](../timer/struct.Timer.html), delivered to subscribers as they happen, so that UIs
+//! need not poll and diff their fields.
+//!
+//! ## Usage
+//!
+//! - Call `.subscribe(<callback>)` on a stopwatch or timer to have `<callback>` called with
+//!   each [`Event`](enum.Event.html), or `.subscribe_channel(<sender>)` to have each event sent
+//!   through an `std::sync::mpsc::Sender`.
+//! - A timer cannot notice its own expiry while nobody touches it. Call `Timer::update()`
+//!   regularly (e.g. whenever you redraw) to have `WarningReached` and `Expired` emitted as soon
+//!   as they are due, in the order they became due.
+
+use chrono::{DateTime, Duration, Local};
+use std::{
+    fmt,
+    sync::{mpsc::Sender, Arc},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Resumed for the first time
+    Started {
+        moment: DateTime<Local>,
+    },
+    Paused {
+        moment: DateTime<Local>,
+    },
+    Resumed {
+        moment: DateTime<Local>,
+    },
+    Lapped {
+        moment: DateTime<Local>,
+        lap: Duration,
+    },
+    Split {
+        moment: DateTime<Local>,
+        elapsed: Duration,
+    },
+    Stopped {
+        moment: DateTime<Local>,
+    },
+    /// The remaining time of a timer reached zero. `moment` is the exact moment of expiry.
+    Expired {
+        moment: DateTime<Local>,
+    },
+    /// Time was added to or taken off a timer. `remaining` is the time remaining after.
+    Adjusted {
+        moment: DateTime<Local>,
+        remaining: Duration,
+    },
+    /// The remaining time of a timer crossed a warning threshold
+    WarningReached {
+        moment: DateTime<Local>,
+        remaining: Duration,
+    },
+}
+
+type Callback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// The subscribers of a stopwatch or timer
+#[derive(Clone, Default)]
+pub(crate) struct Subscribers(Vec<Callback>);
+
+impl Subscribers {
+    pub(crate) fn subscribe(&mut self, callback: impl Fn(&Event) + Send + Sync + 'static) {
+        self.0.push(Arc::new(callback));
+    }
+    pub(crate) fn subscribe_channel(&mut self, sender: Sender<Event>) {
+        // a dropped receiver only means nobody is listening any more
+        self.subscribe(move |event| {
+            sender.send(*event).ok();
+        });
+    }
+    pub(crate) fn emit(&self, event: Event) {
+        for callback in &self.0 {
+            callback(&event);
+        }
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Subscribers({})", self.0.len())
+    }
+}
@@ -0,0 +1,319 @@
+//! # Format
+//!
+//! Human-readable formatting of the durations read from
+//! [`Stopwatch`](../stopwatch/struct.Stopwatch.html) and [`Timer`](../timer/struct.Timer.html).
+//!
+//! ## Usage
+//!
+//! - Call `format(<duration>, <style>)` to format in a [`Style`](enum.Style.html), truncating
+//!   to the smallest unit shown, or build a [`DurationFormat`](struct.DurationFormat.html) to
+//!   choose the [`Rounding`](enum.Rounding.html).
+//! - Negative durations (e.g. a timer in overtime) are prefixed with `-`, unless they round to
+//!   zero. In `Verbose`, a negative of more than one unit is parenthesised, as in
+//!   `-(1 hour, 5 minutes, 3 seconds)`.
+//!
+//! | Style        | 1 hour, 5 minutes and 3.256 seconds |
+//! | ------------ | ----------------------------------- |
+//! | `Stopwatch`  | `1:05:03.25` (`MM:SS.cc` under an hour) |
+//! | `Clock`      | `1:05:03`                           |
+//! | `Compact`    | `1h 5m 3s`                          |
+//! | `Verbose`    | `1 hour, 5 minutes, 3 seconds`      |
+//! | `Iso8601`    | `PT1H5M3S`                          |
+
+use crate::clock::{as_nanos, NANOS_PER_SEC};
+use chrono::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Style {
+    /// `MM:SS.cc` like iOS's stopwatch, or `H:MM:SS.cc` from an hour; precise to centiseconds
+    Stopwatch,
+    /// `H:MM:SS`
+    Clock,
+    /// `1d 2h 5m 3s`, omitting zero units
+    Compact,
+    /// `1 day, 2 hours, 5 minutes, 3 seconds`, omitting zero units
+    Verbose,
+    /// `PT26H5M3S`
+    Iso8601,
+}
+
+impl Style {
+    /// The smallest unit shown, in nanoseconds
+    fn precision(self) -> i128 {
+        match self {
+            Style::Stopwatch => NANOS_PER_SEC / 100,
+            _ => NANOS_PER_SEC,
+        }
+    }
+}
+
+/// How a duration is rounded to the smallest unit shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rounding {
+    /// Towards zero. A stopwatch shows `0:59` until a full minute has passed.
+    Truncate,
+    /// To the nearest unit, halves away from zero
+    Nearest,
+    /// Away from zero. A timer shows `0:01` until it expires.
+    Up,
+}
+
+/// A formatting style with a rounding mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DurationFormat {
+    pub style: Style,
+    pub rounding: Rounding,
+}
+
+impl DurationFormat {
+    /// `style`, truncating
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            rounding: Rounding::Truncate,
+        }
+    }
+
+    pub fn format(&self, duration: Duration) -> String {
+        let precision = self.style.precision();
+        let nanos = as_nanos(duration);
+        let (quotient, remainder) = (nanos.abs() / precision, nanos.abs() % precision);
+        let units = match self.rounding {
+            Rounding::Truncate => quotient,
+            Rounding::Nearest if remainder * 2 >= precision => quotient + 1,
+            Rounding::Nearest => quotient,
+            Rounding::Up if remainder > 0 => quotient + 1,
+            Rounding::Up => quotient,
+        };
+        let sign = if nanos < 0 && units > 0 { "-" } else { "" };
+        let parts = Parts::new(units * precision);
+        let body = match self.style {
+            Style::Stopwatch => parts.stopwatch(),
+            Style::Clock => parts.clock(),
+            Style::Compact => parts.compact(),
+            Style::Verbose => parts.verbose(),
+            Style::Iso8601 => parts.iso8601(),
+        };
+        if !sign.is_empty() && self.style == Style::Verbose && body.contains(", ") {
+            format!("{}({})", sign, body)
+        } else {
+            format!("{}{}", sign, body)
+        }
+    }
+}
+
+impl From<Style> for DurationFormat {
+    fn from(style: Style) -> Self {
+        Self::new(style)
+    }
+}
+
+/// Format `duration` in `style`, truncating
+pub fn format(duration: Duration, style: Style) -> String {
+    DurationFormat::new(style).format(duration)
+}
+
+/// A non-negative duration broken into units
+struct Parts {
+    hours: i128, // total hours, including those making up days
+    minutes: i128,
+    seconds: i128,
+    centis: i128,
+}
+
+impl Parts {
+    fn new(nanos: i128) -> Self {
+        let seconds = nanos / NANOS_PER_SEC;
+        Self {
+            hours: seconds / 3600,
+            minutes: seconds / 60 % 60,
+            seconds: seconds % 60,
+            centis: nanos % NANOS_PER_SEC / (NANOS_PER_SEC / 100),
+        }
+    }
+    fn stopwatch(&self) -> String {
+        if self.hours > 0 {
+            format!(
+                "{}:{:02}:{:02}.{:02}",
+                self.hours, self.minutes, self.seconds, self.centis
+            )
+        } else {
+            format!("{:02}:{:02}.{:02}", self.minutes, self.seconds, self.centis)
+        }
+    }
+    fn clock(&self) -> String {
+        format!("{}:{:02}:{:02}", self.hours, self.minutes, self.seconds)
+    }
+    /// `(days, hours, minutes, seconds)`
+    fn units(&self) -> [i128; 4] {
+        [self.hours / 24, self.hours % 24, self.minutes, self.seconds]
+    }
+    fn compact(&self) -> String {
+        let parts: Vec<String> = self
+            .units()
+            .iter()
+            .zip(&["d", "h", "m", "s"])
+            .filter(|(value, _)| **value > 0)
+            .map(|(value, unit)| format!("{}{}", value, unit))
+            .collect();
+        if parts.is_empty() {
+            "0s".to_owned()
+        } else {
+            parts.join(" ")
+        }
+    }
+    fn verbose(&self) -> String {
+        let parts: Vec<String> = self
+            .units()
+            .iter()
+            .zip(&["day", "hour", "minute", "second"])
+            .filter(|(value, _)| **value > 0)
+            .map(|(value, unit)| plural(*value, unit))
+            .collect();
+        if parts.is_empty() {
+            plural(0, "second")
+        } else {
+            parts.join(", ")
+        }
+    }
+    fn iso8601(&self) -> String {
+        let mut s = "PT".to_owned();
+        for (value, unit) in [self.hours, self.minutes, self.seconds]
+            .iter()
+            .zip(&['H', 'M', 'S'])
+        {
+            if *value > 0 {
+                s.push_str(&format!("{}{}", value, unit));
+            }
+        }
+        if s.len() == 2 {
+            s.push_str("0S");
+        }
+        s
+    }
+}
+
+fn plural(value: i128, unit: &str) -> String {
+    if value == 1 {
+        format!("{} {}", value, unit)
+    } else {
+        format!("{} {}s", value, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 hour, 5 minutes and 3.256 seconds
+    fn example() -> Duration {
+        Duration::milliseconds(3_903_256)
+    }
+
+    fn rounded(duration: Duration, style: Style, rounding: Rounding) -> String {
+        DurationFormat { style, rounding }.format(duration)
+    }
+
+    #[test]
+    fn styles() {
+        assert_eq!(format(example(), Style::Stopwatch), "1:05:03.25");
+        assert_eq!(format(example(), Style::Clock), "1:05:03");
+        assert_eq!(format(example(), Style::Compact), "1h 5m 3s");
+        assert_eq!(
+            format(example(), Style::Verbose),
+            "1 hour, 5 minutes, 3 seconds"
+        );
+        assert_eq!(format(example(), Style::Iso8601), "PT1H5M3S");
+    }
+
+    #[test]
+    fn stopwatch_under_and_over_an_hour() {
+        let almost = Duration::hours(1) - Duration::milliseconds(10);
+        assert_eq!(format(almost, Style::Stopwatch), "59:59.99");
+        assert_eq!(format(Duration::hours(1), Style::Stopwatch), "1:00:00.00");
+        assert_eq!(format(Duration::hours(26), Style::Stopwatch), "26:00:00.00");
+        assert_eq!(format(Duration::zero(), Style::Stopwatch), "00:00.00");
+    }
+
+    #[test]
+    fn days_and_zero_units() {
+        let duration = Duration::hours(26) + Duration::seconds(3);
+        assert_eq!(format(duration, Style::Clock), "26:00:03");
+        assert_eq!(format(duration, Style::Compact), "1d 2h 3s");
+        assert_eq!(
+            format(duration, Style::Verbose),
+            "1 day, 2 hours, 3 seconds"
+        );
+        assert_eq!(format(duration, Style::Iso8601), "PT26H3S");
+        assert_eq!(format(Duration::zero(), Style::Compact), "0s");
+        assert_eq!(format(Duration::zero(), Style::Verbose), "0 seconds");
+        assert_eq!(format(Duration::zero(), Style::Iso8601), "PT0S");
+    }
+
+    #[test]
+    fn rounding() {
+        let duration = Duration::milliseconds(59_500);
+        assert_eq!(
+            rounded(duration, Style::Clock, Rounding::Truncate),
+            "0:00:59"
+        );
+        assert_eq!(
+            rounded(duration, Style::Clock, Rounding::Nearest),
+            "0:01:00"
+        );
+        assert_eq!(rounded(duration, Style::Clock, Rounding::Up), "0:01:00");
+
+        let duration = Duration::milliseconds(59_499);
+        assert_eq!(rounded(duration, Style::Compact, Rounding::Nearest), "59s");
+        assert_eq!(rounded(duration, Style::Compact, Rounding::Up), "1m");
+
+        let duration = Duration::microseconds(12_345);
+        assert_eq!(
+            rounded(duration, Style::Stopwatch, Rounding::Truncate),
+            "00:00.01"
+        );
+        assert_eq!(
+            rounded(duration, Style::Stopwatch, Rounding::Nearest),
+            "00:00.01"
+        );
+        assert_eq!(
+            rounded(duration, Style::Stopwatch, Rounding::Up),
+            "00:00.02"
+        );
+        assert_eq!(
+            rounded(Duration::seconds(5), Style::Clock, Rounding::Up),
+            "0:00:05"
+        );
+    }
+
+    #[test]
+    fn negative() {
+        assert_eq!(format(-example(), Style::Stopwatch), "-1:05:03.25");
+        assert_eq!(format(-example(), Style::Clock), "-1:05:03");
+        assert_eq!(format(-example(), Style::Compact), "-1h 5m 3s");
+        assert_eq!(
+            format(-example(), Style::Verbose),
+            "-(1 hour, 5 minutes, 3 seconds)"
+        );
+        assert_eq!(format(-Duration::minutes(5), Style::Verbose), "-5 minutes");
+        assert_eq!(format(-example(), Style::Iso8601), "-PT1H5M3S");
+        // away from zero, as for positive durations
+        let duration = -Duration::milliseconds(1_500);
+        assert_eq!(
+            rounded(duration, Style::Clock, Rounding::Truncate),
+            "-0:00:01"
+        );
+        assert_eq!(
+            rounded(duration, Style::Clock, Rounding::Nearest),
+            "-0:00:02"
+        );
+        assert_eq!(rounded(duration, Style::Clock, Rounding::Up), "-0:00:02");
+        // rounding to zero drops the sign
+        let duration = -Duration::milliseconds(400);
+        assert_eq!(format(duration, Style::Compact), "0s");
+        assert_eq!(rounded(duration, Style::Compact, Rounding::Up), "-1s");
+    }
+}
@@ -0,0 +1,113 @@
+//! Bounded undo/redo stacks of snapshots, shared by [`Stopwatch`](../stopwatch/struct.Stopwatch.html)
+//! and [`Timer`](../timer/struct.Timer.html).
+
+use std::collections::VecDeque;
+
+/// The number of operations that can be undone by default
+pub(crate) const DEFAULT_LIMIT: usize = 32;
+
+#[derive(Debug, Clone)]
+pub(crate) struct History<T> {
+    undo: VecDeque<T>, // oldest first
+    redo: Vec<T>,      // most recently undone last
+    limit: usize,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl<T> History<T> {
+    /// Record the state before an operation. Operations undone so far can no longer be redone.
+    pub(crate) fn record(&mut self, state: T) {
+        self.redo.clear();
+        if self.limit == 0 {
+            return;
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(state);
+    }
+    /// The state before the last operation, given the `current` one to redo it. Check
+    /// `.can_undo()` first.
+    pub(crate) fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+    /// The state after the last operation undone, given the `current` one to undo it again.
+    /// Check `.can_redo()` first.
+    pub(crate) fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_limit(limit: usize) -> History<u32> {
+        let mut history = History::default();
+        history.set_limit(limit);
+        history
+    }
+
+    #[test]
+    fn evicts_the_oldest_at_the_limit() {
+        let mut history = with_limit(3);
+        for state in 0..5 {
+            history.record(state);
+        }
+        assert_eq!(history.undo(5), Some(4));
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+        assert!(!history.can_undo());
+
+        history.set_limit(1);
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), Some(4));
+        history.set_limit(1);
+        assert_eq!(history.undo(4), Some(3));
+        assert!(!history.can_undo());
+
+        let mut history = with_limit(0);
+        history.record(0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = with_limit(3);
+        history.record(0);
+        history.record(1);
+        assert_eq!(history.undo(2), Some(1));
+        assert!(history.can_redo());
+        history.record(1);
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(2), None);
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), Some(0));
+    }
+}
@@ -1,2 +1,23 @@
+pub mod alarm;
+pub mod calendar;
+pub mod clock;
+pub mod countdown;
+pub mod error;
+pub mod event;
+pub mod format;
+mod history;
+pub mod manager;
+pub mod parse;
+pub mod pomodoro;
+pub mod segment;
+pub mod sequence;
+#[cfg(feature = "serde")]
+mod ser;
+pub mod shared;
 pub mod stopwatch;
+#[cfg(feature = "tokio")]
+pub mod tick;
 pub mod timer;
+pub mod tracking;
+#[cfg(feature = "chrono-tz")]
+pub mod world_clock;
@@ -0,0 +1,348 @@
+//! # Timer manager
+//!
+//! Many labelled [`Timer`](../timer/struct.Timer.html)s and
+//! [`Stopwatch`](../stopwatch/struct.Stopwatch.html)es running at once, e.g. in a kitchen or a
+//! lab.
+//!
+//! ## Usage
+//!
+//! - Use `TimerManager::new()` to initialise an empty manager, and `.add_timer(<label>,
+//!   <duration>)` or `.add_stopwatch(<label>)` to add to it. Each returns the [`Id`](struct.Id.html)
+//!   by which you can get the timer or stopwatch back with `.timer_mut(<id>)` or
+//!   `.stopwatch_mut(<id>)`.
+//! - Call `.pause_all()`, `.resume_all()` or `.stop_all()` to control all of them at once.
+//! - Call `.by_expiry()` to list the timers, soonest to expire first.
+//! - Call `.poll()` regularly (e.g. whenever you redraw) for the timers that have expired since
+//!   the last poll. Each expiry is reported once.
+//! - Call `.stop(<id>)` or `.stop_all()` to stop (reset); the data of stopped timers and
+//!   stopwatches is archived in `.history`.
+
+use crate::clock::{ClockSource, SystemClock};
+use crate::stopwatch::{Stopwatch, StopwatchData};
+use crate::timer::{Timer, TimerData};
+use chrono::{DateTime, Duration, Local};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Identifies a timer or stopwatch in a [`TimerManager`](struct.TimerManager.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Id(pub u64);
+
+/// The data of a stopped timer or stopwatch
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Finished {
+    Timer(TimerData),
+    Stopwatch(StopwatchData),
+}
+
+/// An entry of [`TimerManager::history`](struct.TimerManager.html#structfield.history)
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Archived {
+    pub id: Id,
+    pub label: String,
+    pub data: Finished,
+}
+
+/// Labelled timers and stopwatches
+#[derive(Debug)]
+pub struct TimerManager<C = SystemClock> {
+    pub timers: BTreeMap<Id, (String, Timer<C>)>,
+    pub stopwatches: BTreeMap<Id, (String, Stopwatch<C>)>,
+    pub history: Vec<Archived>,
+    next_id: u64,
+    reported: BTreeSet<Id>, // expired timers already returned by `.poll()`
+    clock: C,
+}
+
+impl Default for TimerManager {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl TimerManager {
+    /// Returns an empty manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C: ClockSource + Clone> TimerManager<C> {
+    /// `TimerManager::new()` on another [clock source](../clock/index.html), shared by its
+    /// timers and stopwatches
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            timers: BTreeMap::new(),
+            stopwatches: BTreeMap::new(),
+            history: Vec::new(),
+            next_id: 0,
+            reported: BTreeSet::new(),
+            clock,
+        }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// Add a paused timer set to `duration`
+    pub fn add_timer(&mut self, label: impl Into<String>, duration: Duration) -> Id {
+        let id = self.next_id();
+        let timer = Timer::with_clock(duration, self.clock.clone());
+        self.timers.insert(id, (label.into(), timer));
+        id
+    }
+    /// Add a paused stopwatch
+    pub fn add_stopwatch(&mut self, label: impl Into<String>) -> Id {
+        let id = self.next_id();
+        let stopwatch = Stopwatch::with_clock(self.clock.clone());
+        self.stopwatches.insert(id, (label.into(), stopwatch));
+        id
+    }
+    pub fn timer(&self, id: Id) -> Option<&Timer<C>> {
+        self.timers.get(&id).map(|(_, timer)| timer)
+    }
+    pub fn timer_mut(&mut self, id: Id) -> Option<&mut Timer<C>> {
+        self.timers.get_mut(&id).map(|(_, timer)| timer)
+    }
+    pub fn stopwatch(&self, id: Id) -> Option<&Stopwatch<C>> {
+        self.stopwatches.get(&id).map(|(_, stopwatch)| stopwatch)
+    }
+    pub fn stopwatch_mut(&mut self, id: Id) -> Option<&mut Stopwatch<C>> {
+        self.stopwatches
+            .get_mut(&id)
+            .map(|(_, stopwatch)| stopwatch)
+    }
+    pub fn label(&self, id: Id) -> Option<&str> {
+        self.timers
+            .get(&id)
+            .map(|(label, _)| label)
+            .or_else(|| self.stopwatches.get(&id).map(|(label, _)| label))
+            .map(String::as_str)
+    }
+    /// Remove a timer or stopwatch without archiving it. Returns whether it existed.
+    pub fn remove(&mut self, id: Id) -> bool {
+        self.reported.remove(&id);
+        self.timers.remove(&id).is_some() || self.stopwatches.remove(&id).is_some()
+    }
+    /// Pause every running timer and stopwatch.
+    pub fn pause_all(&mut self) {
+        self.timers
+            .values_mut()
+            .for_each(|(_, timer)| timer.pause());
+        self.stopwatches
+            .values_mut()
+            .for_each(|(_, stopwatch)| stopwatch.pause());
+    }
+
+    pub fn pause_all_at(&mut self, moment: DateTime<Local>) {
+        self.timers
+            .values_mut()
+            .for_each(|(_, timer)| timer.pause_at(moment));
+        self.stopwatches
+            .values_mut()
+            .for_each(|(_, stopwatch)| stopwatch.pause_at(moment));
+    }
+    /// Resume every paused timer and stopwatch.
+    pub fn resume_all(&mut self) {
+        self.timers
+            .values_mut()
+            .for_each(|(_, timer)| timer.resume());
+        self.stopwatches
+            .values_mut()
+            .for_each(|(_, stopwatch)| stopwatch.resume());
+    }
+
+    pub fn resume_all_at(&mut self, moment: DateTime<Local>) {
+        self.timers
+            .values_mut()
+            .for_each(|(_, timer)| timer.resume_at(moment));
+        self.stopwatches
+            .values_mut()
+            .for_each(|(_, stopwatch)| stopwatch.resume_at(moment));
+    }
+    /// Stop (reset) a timer or stopwatch, archiving its data if it was ever started. Returns
+    /// whether it existed.
+    pub fn stop(&mut self, id: Id) -> bool {
+        self.stop_with(id, None)
+    }
+
+    pub fn stop_at(&mut self, id: Id, moment: DateTime<Local>) -> bool {
+        self.stop_with(id, Some(moment))
+    }
+    /// Stop (reset) every timer and stopwatch, archiving the data of those ever started.
+    pub fn stop_all(&mut self) {
+        for id in self.ids() {
+            self.stop_with(id, None);
+        }
+    }
+
+    pub fn stop_all_at(&mut self, moment: DateTime<Local>) {
+        for id in self.ids() {
+            self.stop_with(id, Some(moment));
+        }
+    }
+    /// The timers, soonest to expire first. Paused timers are ordered as if resumed now.
+    pub fn by_expiry(&self) -> Vec<(Id, &str, &Timer<C>)> {
+        self.by_expiry_at(self.clock.now())
+    }
+
+    pub fn by_expiry_at(&self, moment: DateTime<Local>) -> Vec<(Id, &str, &Timer<C>)> {
+        let mut timers: Vec<_> = self
+            .timers
+            .iter()
+            .map(|(id, (label, timer))| (*id, label.as_str(), timer))
+            .collect();
+        timers.sort_by_key(|(id, _, timer)| {
+            let expiry = timer
+                .expires_at()
+                .unwrap_or_else(|| moment + timer.read_at(moment));
+            (expiry, *id)
+        });
+        timers
+    }
+    /// The timers that have expired since the last poll, in order of expiry
+    pub fn poll(&mut self) -> Vec<Id> {
+        self.poll_at(self.clock.now())
+    }
+
+    pub fn poll_at(&mut self, moment: DateTime<Local>) -> Vec<Id> {
+        let expired: BTreeSet<Id> = self
+            .timers
+            .iter()
+            .filter(|(_, (_, timer))| timer.is_expired_at(moment))
+            .map(|(id, _)| *id)
+            .collect();
+        let mut new: Vec<Id> = expired.difference(&self.reported).copied().collect();
+        new.sort_by_key(|id| (self.timers[id].1.expires_at(), *id));
+        // forget timers that are no longer expired (e.g. stopped), so they are reported again
+        self.reported = expired;
+        new
+    }
+
+    fn next_id(&mut self) -> Id {
+        self.next_id += 1;
+        Id(self.next_id)
+    }
+
+    fn ids(&self) -> Vec<Id> {
+        self.timers
+            .keys()
+            .chain(self.stopwatches.keys())
+            .copied()
+            .collect()
+    }
+
+    fn stop_with(&mut self, id: Id, moment: Option<DateTime<Local>>) -> bool {
+        let (label, data, started) = if let Some((label, timer)) = self.timers.get_mut(&id) {
+            let started = !timer.data.start_moments.is_empty();
+            let data = match moment {
+                Some(moment) => timer.stop_at(moment),
+                None => timer.stop(),
+            };
+            (label.clone(), Finished::Timer(data), started)
+        } else if let Some((label, stopwatch)) = self.stopwatches.get_mut(&id) {
+            let started = !stopwatch.data.start_moments.is_empty();
+            let data = match moment {
+                Some(moment) => stopwatch.stop_at(moment),
+                None => stopwatch.stop(),
+            };
+            (label.clone(), Finished::Stopwatch(data), started)
+        } else {
+            return false;
+        };
+        if started {
+            self.history.push(Archived { id, label, data });
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: i64) -> Duration {
+        Duration::minutes(minutes)
+    }
+
+    /// Timers of 10 and 5 minutes, started together, and a stopwatch never started
+    fn kitchen() -> (TimerManager, [Id; 3], DateTime<Local>) {
+        let start = Local::now();
+        let mut manager = TimerManager::new();
+        let pasta = manager.add_timer("pasta", minutes(10));
+        let eggs = manager.add_timer("eggs", minutes(5));
+        let oven = manager.add_stopwatch("oven");
+        manager.timer_mut(pasta).unwrap().resume_at(start);
+        manager.timer_mut(eggs).unwrap().resume_at(start);
+        (manager, [pasta, eggs, oven], start)
+    }
+
+    #[test]
+    fn poll_reports_each_expiry_once() {
+        let (mut manager, [pasta, eggs, _], start) = kitchen();
+        assert!(manager.poll_at(start + minutes(4)).is_empty());
+        assert_eq!(manager.poll_at(start + minutes(6)), vec![eggs]);
+        assert!(manager.poll_at(start + minutes(7)).is_empty());
+        assert_eq!(manager.poll_at(start + minutes(11)), vec![pasta]);
+
+        // in order of expiry
+        let (mut manager, [pasta, eggs, _], start) = kitchen();
+        assert_eq!(manager.poll_at(start + minutes(11)), vec![eggs, pasta]);
+    }
+
+    #[test]
+    fn poll_reports_again_after_a_reset() {
+        let (mut manager, [pasta, eggs, _], start) = kitchen();
+        assert_eq!(manager.poll_at(start + minutes(6)), vec![eggs]);
+        let timer = manager.timer_mut(eggs).unwrap();
+        timer.restart_at(start + minutes(7));
+        assert!(manager.poll_at(start + minutes(8)).is_empty());
+        assert_eq!(manager.poll_at(start + minutes(12)), vec![pasta, eggs]);
+
+        manager.stop_at(eggs, start + minutes(13));
+        manager
+            .timer_mut(eggs)
+            .unwrap()
+            .resume_at(start + minutes(14));
+        assert!(manager.poll_at(start + minutes(15)).is_empty());
+        assert_eq!(manager.poll_at(start + minutes(19)), vec![eggs]);
+    }
+
+    #[test]
+    fn by_expiry() {
+        let (mut manager, [pasta, eggs, _], start) = kitchen();
+        let rice = manager.add_timer("rice", minutes(7));
+        let order = |manager: &TimerManager| -> Vec<Id> {
+            manager
+                .by_expiry_at(start + minutes(1))
+                .iter()
+                .map(|(id, _, _)| *id)
+                .collect()
+        };
+        // the paused rice timer as if resumed a minute in
+        assert_eq!(order(&manager), vec![eggs, rice, pasta]);
+        manager
+            .timer_mut(pasta)
+            .unwrap()
+            .subtract_time_at(start + minutes(1), minutes(6));
+        assert_eq!(order(&manager), vec![pasta, eggs, rice]);
+    }
+
+    #[test]
+    fn stop_all_archives_those_started() {
+        let (mut manager, [pasta, eggs, oven], start) = kitchen();
+        assert_eq!(manager.label(oven), Some("oven"));
+        manager.stop_all_at(start + minutes(3));
+        let archived: Vec<(Id, &str)> = manager
+            .history
+            .iter()
+            .map(|archived| (archived.id, archived.label.as_str()))
+            .collect();
+        assert_eq!(archived, vec![(pasta, "pasta"), (eggs, "eggs")]);
+        assert!(manager.remove(oven));
+        assert!(!manager.remove(oven));
+        assert!(!manager.stop(oven));
+    }
+}
@@ -0,0 +1,470 @@
+//! # Parse
+//!
+//! Parsing of the durations and moments people type when setting a timer.
+//!
+//! ## Usage
+//!
+//! - Call `parse_duration(<input>)` for a duration: `25m`, `1h30m`, `1.5 hours`, `90s`,
+//!   `2 minutes and 30 seconds`, `1:30:00` (`H:MM:SS`), `1:30` (`M:SS`) or `in 10 minutes`.
+//! - Call `parse(<input>)` to also accept a moment: `at 14:30`, `at 9:30pm`, `today noon` or
+//!   `tomorrow 9am`. A moment given with `at` alone is the next occurrence of that time. Get a
+//!   duration (e.g. for `Timer::new`) from the resulting
+//!   [`TimeExpression`](enum.TimeExpression.html) with `.duration_from(<moment>)`.
+//! - Input is case-insensitive. Errors report the byte offset of the offending input.
+//!
+//! ## Examples
+//!
+//! ```
+//! use chrono::Duration;
+//! use clock_core::parse::parse_duration;
+//!
+//! assert_eq!(parse_duration("1h 30m"), Ok(Duration::minutes(90)));
+//! assert_eq!(parse_duration("1:30"), Ok(Duration::seconds(90)));
+//! assert_eq!(parse_duration("30 lightyears").unwrap_err().position, 3);
+//! ```
+
+use crate::clock::NANOS_PER_SEC;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use std::{convert::TryFrom, error::Error, fmt};
+
+/// What went wrong while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input is blank
+    Empty,
+    ExpectedNumber,
+    /// A number not followed by a unit, e.g. `25`
+    MissingUnit,
+    UnknownUnit(String),
+    /// Not a valid time of day, e.g. `25:00` or `13pm`
+    InvalidTime,
+    /// A time of day skipped over when clocks go forward
+    NonexistentTime,
+    /// A duration too long to represent
+    Overflow,
+    /// Input left over after a complete expression
+    Unexpected,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "empty input"),
+            ParseErrorKind::ExpectedNumber => write!(f, "expected a number"),
+            ParseErrorKind::MissingUnit => write!(f, "missing unit (e.g. `h`, `m` or `s`)"),
+            ParseErrorKind::UnknownUnit(unit) => write!(f, "unknown unit `{}`", unit),
+            ParseErrorKind::InvalidTime => write!(f, "invalid time of day"),
+            ParseErrorKind::NonexistentTime => write!(f, "time of day skipped by a clock change"),
+            ParseErrorKind::Overflow => write!(f, "duration too long"),
+            ParseErrorKind::Unexpected => write!(f, "unexpected input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize, // byte offset into the input
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+/// A parsed duration or moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeExpression {
+    Duration(Duration),
+    Moment(DateTime<Local>),
+}
+
+impl TimeExpression {
+    /// The duration itself, or the time from `moment` until the moment
+    pub fn duration_from(&self, moment: DateTime<Local>) -> Duration {
+        match self {
+            TimeExpression::Duration(duration) => *duration,
+            TimeExpression::Moment(target) => *target - moment,
+        }
+    }
+}
+
+/// Parse a duration, e.g. `1h30m`, `90s`, `1:30:00` or `in 10 minutes`
+pub fn parse_duration(input: &str) -> Result<Duration, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.non_empty()?;
+    parser.word("in");
+    let duration = parser.duration()?;
+    parser.end()?;
+    Ok(duration)
+}
+
+/// Parse a duration, or a moment such as `at 14:30` or `tomorrow 9am`
+pub fn parse(input: &str) -> Result<TimeExpression, ParseError> {
+    parse_at(input, Local::now())
+}
+
+/// Parse a duration, or a moment relative to `moment`
+pub fn parse_at(input: &str, moment: DateTime<Local>) -> Result<TimeExpression, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.non_empty()?;
+    let days = if parser.word("today") {
+        Some(0)
+    } else if parser.word("tomorrow") {
+        Some(1)
+    } else {
+        None
+    };
+    let at = parser.word("at");
+    if days.is_none() && !at {
+        return parse_duration(input).map(TimeExpression::Duration);
+    }
+    let position = parser.skip_whitespace();
+    let time = parser.time()?;
+    parser.end()?;
+    let date = moment.date_naive() + Duration::days(days.unwrap_or(0));
+    let mut target = Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or(ParseError {
+            position,
+            kind: ParseErrorKind::NonexistentTime,
+        })?;
+    if days.is_none() && target <= moment {
+        // `at` alone means the next occurrence
+        target = Local
+            .from_local_datetime(&(date + Duration::days(1)).and_time(time))
+            .earliest()
+            .ok_or(ParseError {
+                position,
+                kind: ParseErrorKind::NonexistentTime,
+            })?;
+    }
+    Ok(TimeExpression::Moment(target))
+}
+
+struct Parser {
+    input: String, // lowercased; ASCII lowercasing keeps byte offsets
+    position: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Self {
+        Self {
+            input: input.to_ascii_lowercase(),
+            position: 0,
+        }
+    }
+    fn rest(&self) -> &str {
+        &self.input[self.position..]
+    }
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.position,
+            kind,
+        }
+    }
+    fn skip_whitespace(&mut self) -> usize {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+        self.position
+    }
+    fn non_empty(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            Err(self.error(ParseErrorKind::Empty))
+        } else {
+            Ok(())
+        }
+    }
+    fn end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::Unexpected))
+        }
+    }
+    /// Consume `word` (as a whole word) if it comes next
+    fn word(&mut self, word: &str) -> bool {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let matches = rest.starts_with(word)
+            && !rest[word.len()..].starts_with(|c: char| c.is_ascii_alphanumeric());
+        if matches {
+            self.position += word.len();
+        }
+        matches
+    }
+    /// Consume a run of characters satisfying `predicate`
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let start = self.position;
+        let len = self
+            .rest()
+            .find(|c| !predicate(c))
+            .unwrap_or(self.rest().len());
+        self.position += len;
+        &self.input[start..self.position]
+    }
+    fn integer(&mut self) -> Result<i128, ParseError> {
+        let position = self.position;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(ParseError {
+                position,
+                kind: ParseErrorKind::ExpectedNumber,
+            });
+        }
+        digits.parse().map_err(|_| ParseError {
+            position,
+            kind: ParseErrorKind::Overflow,
+        })
+    }
+    /// `1h30m`, `1.5 hours`, `2 minutes and 30 seconds`, `1:30:00` or `1:30`
+    fn duration(&mut self) -> Result<Duration, ParseError> {
+        let start = self.skip_whitespace();
+        let first = self.integer()?;
+        let nanos = if self.rest().starts_with(':') {
+            self.clock_duration(start, first)?
+        } else {
+            self.position = start;
+            self.unit_duration()?
+        };
+        i64::try_from(nanos)
+            .map(Duration::nanoseconds)
+            .map_err(|_| ParseError {
+                position: start,
+                kind: ParseErrorKind::Overflow,
+            })
+    }
+    /// The rest of `H:MM:SS` or `M:SS` after the first component (at `start`), in nanoseconds
+    fn clock_duration(&mut self, start: usize, first: i128) -> Result<i128, ParseError> {
+        // only the first component is unbounded, so it is the one too large
+        let overflow = ParseError {
+            position: start,
+            kind: ParseErrorKind::Overflow,
+        };
+        let (mut seconds, mut components) = (first, 1);
+        while self.rest().starts_with(':') && components < 3 {
+            self.position += 1;
+            let position = self.position;
+            let component = self.integer()?;
+            if component >= 60 {
+                return Err(ParseError {
+                    position,
+                    kind: ParseErrorKind::InvalidTime,
+                });
+            }
+            seconds = seconds
+                .checked_mul(60)
+                .and_then(|seconds| seconds.checked_add(component))
+                .ok_or_else(|| overflow.clone())?;
+            components += 1;
+        }
+        seconds.checked_mul(NANOS_PER_SEC).ok_or(overflow)
+    }
+    /// A sequence of numbers with units, in nanoseconds
+    fn unit_duration(&mut self) -> Result<i128, ParseError> {
+        let mut total: i128 = 0;
+        loop {
+            let position = self.skip_whitespace();
+            let integer = self.integer()?;
+            let (fraction, scale) = if self.rest().starts_with('.') {
+                self.position += 1;
+                let fraction_position = self.position;
+                let digits = self.take_while(|c| c.is_ascii_digit()).to_owned();
+                if digits.is_empty() {
+                    return Err(ParseError {
+                        position: fraction_position,
+                        kind: ParseErrorKind::ExpectedNumber,
+                    });
+                }
+                let digits = &digits[..digits.len().min(9)];
+                (digits.parse().unwrap_or(0), 10i128.pow(digits.len() as u32))
+            } else {
+                (0, 1)
+            };
+            let unit_position = self.skip_whitespace();
+            let unit = self.take_while(|c| c.is_ascii_alphabetic()).to_owned();
+            let unit_nanos = match unit.as_str() {
+                "" => {
+                    return Err(ParseError {
+                        position: unit_position,
+                        kind: ParseErrorKind::MissingUnit,
+                    })
+                }
+                "d" | "day" | "days" => 86_400 * NANOS_PER_SEC,
+                "h" | "hr" | "hrs" | "hour" | "hours" => 3_600 * NANOS_PER_SEC,
+                "m" | "min" | "mins" | "minute" | "minutes" => 60 * NANOS_PER_SEC,
+                "s" | "sec" | "secs" | "second" | "seconds" => NANOS_PER_SEC,
+                "ms" | "millisecond" | "milliseconds" => NANOS_PER_SEC / 1_000,
+                _ => {
+                    return Err(ParseError {
+                        position: unit_position,
+                        kind: ParseErrorKind::UnknownUnit(unit),
+                    })
+                }
+            };
+            total = integer
+                .checked_mul(unit_nanos)
+                .and_then(|nanos| nanos.checked_add(fraction * unit_nanos / scale))
+                .and_then(|nanos| nanos.checked_add(total))
+                .ok_or(ParseError {
+                    position,
+                    kind: ParseErrorKind::Overflow,
+                })?;
+            // separators between components, which must be followed by another
+            self.skip_whitespace();
+            let comma = self.rest().starts_with(',');
+            if comma {
+                self.position += 1;
+            }
+            let and = self.word("and");
+            self.skip_whitespace();
+            if !self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+                return if comma || and {
+                    Err(self.error(ParseErrorKind::ExpectedNumber))
+                } else {
+                    Ok(total)
+                };
+            }
+        }
+    }
+    /// `14:30`, `14`, `9am`, `9:30 pm`, `14:30:15`, `noon` or `midnight`
+    fn time(&mut self) -> Result<NaiveTime, ParseError> {
+        let position = self.skip_whitespace();
+        let invalid = ParseError {
+            position,
+            kind: ParseErrorKind::InvalidTime,
+        };
+        if self.word("noon") {
+            return Ok(NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+        }
+        if self.word("midnight") {
+            return Ok(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        }
+        let mut components = vec![self.integer()?];
+        while self.rest().starts_with(':') && components.len() < 3 {
+            self.position += 1;
+            components.push(self.integer()?);
+        }
+        let hour = components[0];
+        let hour = if self.word("am") {
+            match hour {
+                12 => 0,
+                1..=11 => hour,
+                _ => return Err(invalid),
+            }
+        } else if self.word("pm") {
+            match hour {
+                12 => 12,
+                1..=11 => hour + 12,
+                _ => return Err(invalid),
+            }
+        } else {
+            hour
+        };
+        let minute = components.get(1).copied().unwrap_or(0);
+        let second = components.get(2).copied().unwrap_or(0);
+        u32::try_from(hour)
+            .ok()
+            .zip(u32::try_from(minute).ok())
+            .zip(u32::try_from(second).ok())
+            .and_then(|((h, m), s)| NaiveTime::from_hms_opt(h, m, s))
+            .ok_or(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> (usize, ParseErrorKind) {
+        let error = parse_duration(input).unwrap_err();
+        (error.position, error.kind)
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("25m"), Ok(Duration::minutes(25)));
+        assert_eq!(parse_duration("1.5 hours"), Ok(Duration::minutes(90)));
+        assert_eq!(
+            parse_duration("2 minutes and 30 seconds"),
+            Ok(Duration::seconds(150))
+        );
+        assert_eq!(parse_duration("1m, 30s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_duration("1:30:00"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration("In 10 Minutes"), Ok(Duration::minutes(10)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::milliseconds(250)));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            error("1000000000000000000000000000000:00"),
+            (0, ParseErrorKind::Overflow)
+        );
+        assert_eq!(error("in 99999999999:00:00"), (3, ParseErrorKind::Overflow));
+        assert_eq!(
+            error("1m 1000000000000000000000000000000d"),
+            (3, ParseErrorKind::Overflow)
+        );
+        assert_eq!(
+            error("9".repeat(50).as_str()),
+            (0, ParseErrorKind::Overflow)
+        );
+    }
+
+    #[test]
+    fn dangling_separators_and_fractions() {
+        assert_eq!(error("25m and"), (7, ParseErrorKind::ExpectedNumber));
+        assert_eq!(error("5m,"), (3, ParseErrorKind::ExpectedNumber));
+        assert_eq!(error("5m, and x"), (8, ParseErrorKind::ExpectedNumber));
+        assert_eq!(error("1.h"), (2, ParseErrorKind::ExpectedNumber));
+        assert_eq!(error("1:"), (2, ParseErrorKind::ExpectedNumber));
+        assert_eq!(error("5m x"), (3, ParseErrorKind::Unexpected));
+    }
+
+    #[test]
+    fn other_errors() {
+        assert_eq!(error("  "), (2, ParseErrorKind::Empty));
+        assert_eq!(error("25"), (2, ParseErrorKind::MissingUnit));
+        assert_eq!(
+            error("30 lightyears"),
+            (3, ParseErrorKind::UnknownUnit("lightyears".to_owned()))
+        );
+        assert_eq!(error("1:60"), (2, ParseErrorKind::InvalidTime));
+    }
+
+    #[test]
+    fn moments() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap();
+        let at = |input| match parse_at(input, now) {
+            Ok(TimeExpression::Moment(moment)) => moment.naive_local(),
+            other => panic!("{:?}", other),
+        };
+        let day = now.date_naive();
+        assert_eq!(at("at 16:30"), day.and_hms_opt(16, 30, 0).unwrap());
+        // already past today, so tomorrow
+        assert_eq!(
+            at("at 9am"),
+            (day + Duration::days(1)).and_hms_opt(9, 0, 0).unwrap()
+        );
+        assert_eq!(at("today noon"), day.and_hms_opt(12, 0, 0).unwrap());
+        assert_eq!(
+            at("tomorrow 9:30 PM"),
+            (day + Duration::days(1)).and_hms_opt(21, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_at("at 13pm", now).unwrap_err().kind,
+            ParseErrorKind::InvalidTime
+        );
+        assert_eq!(
+            parse_at("in 5m", now),
+            Ok(TimeExpression::Duration(Duration::minutes(5)))
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! # Pomodoro
+//!
+//! A Pomodoro session built on [`Timer`](../timer/struct.Timer.html): work phases alternate
+//! with short breaks, and every few work phases the break is a long one.
+//!
+//! ## Usage
+//!
+//! - Use `Pomodoro::new(<config>)` to initialise a new session, where `<config>` is a
+//!   [`PomodoroConfig`](struct.PomodoroConfig.html) (`PomodoroConfig::default()` is 25 minutes
+//!   of work, 5-minute short breaks and a 15-minute long break after every 4 work phases). The
+//!   session starts at a paused work phase and will **not** run until you call `.resume()` or
+//!   `.pause_or_resume()`.
+//! - Call `.update()` regularly (e.g. whenever you redraw). Each expired phase is recorded in
+//!   `.history` and the next phase begins (and, if `config.auto_start` is set, starts running)
+//!   at the moment the previous one expired.
+//! - Call `.skip()` to end the current phase early, or `.extend(<duration>)` to lengthen it.
+
+use crate::clock::{ClockSource, SystemClock};
+use crate::timer::{Timer, TimerData};
+use chrono::{DateTime, Duration, Local};
+
+/// A phase of a Pomodoro session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PomodoroConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub work: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub short_break: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub long_break: Duration,
+    pub long_break_interval: u32, // number of work phases before each long break
+    pub auto_start: bool,         // start the next phase as soon as the current one expires
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            work: Duration::minutes(25),
+            short_break: Duration::minutes(5),
+            long_break: Duration::minutes(15),
+            long_break_interval: 4,
+            auto_start: true,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// The length of `phase`
+    pub fn duration(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// A Pomodoro session
+#[derive(Debug, Clone)]
+pub struct Pomodoro<C = SystemClock> {
+    pub config: PomodoroConfig,
+    pub phase: Phase,
+    pub work_phases: u32, // number of work phases finished (completed or skipped)
+    pub timer: Timer<C>,  // the timer of the current phase
+    pub history: Vec<(Phase, TimerData)>, // finished phases
+}
+
+impl Pomodoro {
+    /// Returns a session at the beginning of a paused work phase
+    pub fn new(config: PomodoroConfig) -> Self {
+        Self::with_clock(config, SystemClock)
+    }
+}
+
+impl<C: ClockSource + Clone> Pomodoro<C> {
+    /// `Pomodoro::new(config)` on another [clock source](../clock/index.html)
+    pub fn with_clock(config: PomodoroConfig, clock: C) -> Self {
+        Self {
+            config,
+            phase: Phase::Work,
+            work_phases: 0,
+            timer: Timer::with_clock(config.work, clock),
+            history: Vec::new(),
+        }
+    }
+    /// Read the time remaining in the current phase. Does not advance to the next phase; call
+    /// `.update()` for that.
+    pub fn read(&self) -> Duration {
+        self.timer.read()
+    }
+
+    pub fn read_at(&self, moment: DateTime<Local>) -> Duration {
+        self.timer.read_at(moment)
+    }
+    /// The phase that follows the current one
+    pub fn next_phase(&self) -> Phase {
+        match self.phase {
+            Phase::Work => {
+                // an interval of 0 means no long breaks
+                if (self.work_phases + 1).is_multiple_of(self.config.long_break_interval) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        }
+    }
+    /// Advance past every phase that has expired. Returns the phases completed, in order.
+    pub fn update(&mut self) -> Vec<Phase> {
+        self.update_at(self.timer.now())
+    }
+
+    pub fn update_at(&mut self, moment: DateTime<Local>) -> Vec<Phase> {
+        let mut completed = Vec::new();
+        // a zero-length phase would otherwise expire forever; it can still be skipped
+        while self.timer.data.total > Duration::zero() && self.timer.is_expired_at(moment) {
+            let expiry = self.timer.expires_at().unwrap_or(moment);
+            completed.push(self.phase);
+            self.advance_at(expiry, self.config.auto_start);
+        }
+        completed
+    }
+    /// Pause or resume the current phase.
+    pub fn pause_or_resume(&mut self) {
+        if self.timer.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    pub fn pause_or_resume_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        self.timer.pause_or_resume_at(moment);
+    }
+    /// Pause the current phase (suggest using `pause_or_resume` instead.)
+    pub fn pause(&mut self) {
+        self.pause_at(self.timer.now());
+    }
+
+    pub fn pause_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        self.timer.pause_at(moment);
+    }
+    /// Resume the current phase (suggest using `pause_or_resume` instead.)
+    pub fn resume(&mut self) {
+        self.update();
+        self.timer.resume();
+    }
+
+    pub fn resume_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        self.timer.resume_at(moment);
+    }
+    /// End the current phase early and begin the next one, which starts running if the current
+    /// phase was running. Returns the phase skipped.
+    pub fn skip(&mut self) -> Phase {
+        self.skip_at(self.timer.now())
+    }
+
+    pub fn skip_at(&mut self, moment: DateTime<Local>) -> Phase {
+        self.update_at(moment);
+        let skipped = self.phase;
+        self.advance_at(moment, !self.timer.paused);
+        skipped
+    }
+    /// Lengthen the current phase by `duration`.
+    pub fn extend(&mut self, duration: Duration) {
+        self.extend_at(self.timer.now(), duration);
+    }
+
+    pub fn extend_at(&mut self, moment: DateTime<Local>, duration: Duration) {
+        self.update_at(moment);
+        self.timer.add_time_at(moment, duration);
+    }
+
+    /// Finish the current phase at `moment` and begin the next one
+    fn advance_at(&mut self, moment: DateTime<Local>, start: bool) {
+        let next = self.next_phase();
+        let data = self
+            .timer
+            .begin_next_at(moment, self.config.duration(next), start);
+        if !data.start_moments.is_empty() {
+            self.history.push((self.phase, data));
+        }
+        if self.phase == Phase::Work {
+            self.work_phases += 1;
+        }
+        self.phase = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn phases_ignore_wall_clock_jumps() {
+        let clock = ManualClock::new(Local::now());
+        let mut pomodoro = Pomodoro::with_clock(PomodoroConfig::default(), clock.clone());
+        pomodoro.resume();
+        clock.advance(Duration::minutes(10));
+        clock.set(clock.now() + Duration::hours(1)); // e.g. an NTP correction
+        assert_eq!(pomodoro.read(), Duration::minutes(15));
+
+        clock.advance(Duration::minutes(16));
+        assert_eq!(pomodoro.update(), vec![Phase::Work]);
+        assert_eq!(pomodoro.phase, Phase::ShortBreak);
+        clock.set(clock.now() - Duration::hours(2));
+        assert_eq!(pomodoro.read(), Duration::minutes(4));
+    }
+}
@@ -0,0 +1,189 @@
+//! # Segments
+//!
+//! The running periods and paused gaps recorded in
+//! [`StopwatchData`](../stopwatch/struct.StopwatchData.html) and
+//! [`TimerData`](../timer/struct.TimerData.html), so that you need not zip `start_moments` and
+//! `pause_moments` by hand.
+//!
+//! ## Usage
+//!
+//! - Call `.running_segments()` for the periods between each start and the following pause,
+//!   and `.paused_segments()` for the gaps between each pause and the following start. A
+//!   period still running has no end yet and is left out.
+//! - Call `.active_time()` and `.paused_time()` for their totals.
+//! - Call `.validate()` to check that the moments alternate start, pause, start, ... in order,
+//!   e.g. after deserialising or editing the data.
+
+use crate::error::ClockError;
+use chrono::{DateTime, Duration, Local};
+
+/// A period between two moments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub duration: Duration,
+}
+
+impl Span {
+    pub fn new(start: DateTime<Local>, end: DateTime<Local>) -> Self {
+        Self {
+            start,
+            end,
+            duration: end - start,
+        }
+    }
+}
+
+/// Each start paired with the following pause
+pub(crate) fn running<'a>(
+    starts: &'a [DateTime<Local>],
+    pauses: &'a [DateTime<Local>],
+) -> impl Iterator<Item = Span> + 'a {
+    starts
+        .iter()
+        .zip(pauses)
+        .map(|(start, pause)| Span::new(*start, *pause))
+}
+
+/// Each pause paired with the following start
+pub(crate) fn paused<'a>(
+    starts: &'a [DateTime<Local>],
+    pauses: &'a [DateTime<Local>],
+) -> impl Iterator<Item = Span> + 'a {
+    pauses
+        .iter()
+        .zip(starts.iter().skip(1))
+        .map(|(pause, start)| Span::new(*pause, *start))
+}
+
+pub(crate) fn total(segments: impl Iterator<Item = Span>) -> Duration {
+    segments.fold(Duration::zero(), |total, segment| total + segment.duration)
+}
+
+/// Check that `starts` and `pauses` alternate start, pause, start, ... in order
+pub(crate) fn validate(
+    starts: &[DateTime<Local>],
+    pauses: &[DateTime<Local>],
+) -> Result<(), ClockError> {
+    if pauses.len() > starts.len() {
+        // a pause with no start before it
+        return Err(ClockError::NotInterleaved {
+            index: 2 * starts.len() + 1,
+        });
+    }
+    if starts.len() > pauses.len() + 1 {
+        // a start while already running
+        return Err(ClockError::NotInterleaved {
+            index: 2 * pauses.len() + 2,
+        });
+    }
+    let mut moments = Vec::with_capacity(starts.len() + pauses.len());
+    for (i, start) in starts.iter().enumerate() {
+        moments.push(*start);
+        moments.extend(pauses.get(i));
+    }
+    match moments.windows(2).position(|pair| pair[1] < pair[0]) {
+        Some(i) => Err(ClockError::NotInterleaved { index: i + 1 }),
+        None => Ok(()),
+    }
+}
+
+/// Whether the clock was running at `moment`. The last start without a pause runs on.
+pub(crate) fn is_running_at(
+    starts: &[DateTime<Local>],
+    pauses: &[DateTime<Local>],
+    moment: DateTime<Local>,
+) -> bool {
+    starts
+        .iter()
+        .enumerate()
+        .any(|(i, start)| *start <= moment && pauses.get(i).is_none_or(|pause| moment <= *pause))
+}
+
+/// The running time between `from` and `to`
+pub(crate) fn active_between(
+    starts: &[DateTime<Local>],
+    pauses: &[DateTime<Local>],
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+) -> Duration {
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end = pauses.get(i).map_or(to, |pause| to.min(*pause));
+            (end - from.max(*start)).max(Duration::zero())
+        })
+        .fold(Duration::zero(), |total, duration| total + duration)
+}
+
+/// The moment at which the clock had run for `duration` since `from`, or `None` if it had not
+/// by the last pause. The last start without a pause runs on.
+pub(crate) fn after_running(
+    starts: &[DateTime<Local>],
+    pauses: &[DateTime<Local>],
+    from: DateTime<Local>,
+    duration: Duration,
+) -> Option<DateTime<Local>> {
+    let mut left = duration;
+    for (i, start) in starts.iter().enumerate() {
+        let start = from.max(*start);
+        match pauses.get(i) {
+            Some(pause) if *pause < start => {}
+            Some(pause) if *pause - start < left => left -= *pause - start,
+            _ => return Some(start + left),
+        }
+    }
+    None
+}
+
+/// Insert a pause at `pause` and a start at `resume` into the running segment containing both
+pub(crate) fn insert_pause(
+    starts: &mut Vec<DateTime<Local>>,
+    pauses: &mut Vec<DateTime<Local>>,
+    pause: DateTime<Local>,
+    resume: DateTime<Local>,
+) -> Result<(), ClockError> {
+    if resume < pause {
+        return Err(ClockError::OutOfOrder {
+            moment: resume,
+            last: pause,
+        });
+    }
+    let i = starts
+        .iter()
+        .enumerate()
+        .position(|(i, start)| *start <= pause && pauses.get(i).is_none_or(|end| resume <= *end))
+        .ok_or(ClockError::NotRunningAt { moment: pause })?;
+    pauses.insert(i, pause);
+    starts.insert(i + 1, resume);
+    Ok(())
+}
+
+/// The parts of the running segments between `from` and `to` (either unbounded if `None`)
+pub(crate) fn clip(
+    starts: &[DateTime<Local>],
+    pauses: &[DateTime<Local>],
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+) -> (Vec<DateTime<Local>>, Vec<DateTime<Local>>) {
+    let (mut clipped_starts, mut clipped_pauses) = (Vec::new(), Vec::new());
+    for (i, start) in starts.iter().enumerate() {
+        let start = from.map_or(*start, |from| from.max(*start));
+        let end = match (pauses.get(i), to) {
+            (Some(pause), Some(to)) => Some(to.min(*pause)),
+            (pause, to) => pause.copied().or(to),
+        };
+        match end {
+            Some(end) if end <= start => {}
+            _ => {
+                clipped_starts.push(start);
+                clipped_pauses.extend(end);
+            }
+        }
+    }
+    (clipped_starts, clipped_pauses)
+}
@@ -0,0 +1,297 @@
+//! # Sequence
+//!
+//! Chained countdowns for interval training (e.g. HIIT), built on
+//! [`Timer`](../timer/struct.Timer.html).
+//!
+//! ## Usage
+//!
+//! - Describe the program as a tree of [`Segment`](enum.Segment.html)s: an `interval` is a
+//!   named countdown, and a `group` repeats its children a number of times.
+//! - Use `Sequence::new(<program>)` to initialise a new sequence. The sequence is paused at the
+//!   beginning of its first interval and will **not** run until you call `.resume()` or
+//!   `.pause_or_resume()`.
+//! - Call `.update()` regularly (e.g. whenever you redraw). Each expired interval is recorded in
+//!   `.log` and the next one starts at the moment the previous one expired.
+//! - Call `.current()` for the current interval (including its rounds), `.read()` for the time
+//!   remaining in it and `.read_total()` for the time remaining in the whole program.
+//! - Call `.skip()` to end the current interval early.
+//!
+//! ## Examples
+//!
+//! 8 rounds of 20s work / 10s rest, twice, with 60s between the sets:
+//!
+//! ```
+//! use chrono::Duration;
+//! use clock_core::sequence::{Segment, Sequence};
+//!
+//! let tabata = Segment::group(
+//!     "tabata",
+//!     8,
+//!     vec![
+//!         Segment::interval("work", Duration::seconds(20)),
+//!         Segment::interval("rest", Duration::seconds(10)),
+//!     ],
+//! );
+//! let program = vec![
+//!     tabata.clone(),
+//!     Segment::interval("break", Duration::seconds(60)),
+//!     tabata,
+//! ];
+//! let sequence = Sequence::new(program);
+//! assert_eq!(sequence.read_total(), Duration::seconds(540));
+//! ```
+
+use crate::clock::{ClockSource, SystemClock};
+use crate::timer::{Timer, TimerData};
+use chrono::{DateTime, Duration, Local};
+
+/// A node of an interval training program
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Segment {
+    /// A named countdown
+    Interval {
+        name: String,
+        #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+        duration: Duration,
+    },
+    /// `children`, in order, `repeat` times
+    Group {
+        name: String,
+        repeat: u32,
+        children: Vec<Segment>,
+    },
+}
+
+impl Segment {
+    pub fn interval(name: impl Into<String>, duration: Duration) -> Self {
+        Segment::Interval {
+            name: name.into(),
+            duration,
+        }
+    }
+    pub fn group(name: impl Into<String>, repeat: u32, children: Vec<Segment>) -> Self {
+        Segment::Group {
+            name: name.into(),
+            repeat,
+            children,
+        }
+    }
+    /// Append the intervals of this segment, in order, to `steps`
+    fn flatten(&self, rounds: &mut Vec<Round>, steps: &mut Vec<Step>) {
+        match self {
+            Segment::Interval { name, duration } => steps.push(Step {
+                name: name.clone(),
+                duration: *duration,
+                rounds: rounds.clone(),
+            }),
+            Segment::Group {
+                name,
+                repeat,
+                children,
+            } => {
+                for round in 1..=*repeat {
+                    rounds.push(Round {
+                        group: name.clone(),
+                        round,
+                        of: *repeat,
+                    });
+                    for child in children {
+                        child.flatten(rounds, steps);
+                    }
+                    rounds.pop();
+                }
+            }
+        }
+    }
+}
+
+/// The round of a group that an interval belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Round {
+    pub group: String, // name of the group
+    pub round: u32,    // starting from 1
+    pub of: u32,       // number of rounds in the group
+}
+
+/// An interval of a program, in the order it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub duration: Duration,
+    pub rounds: Vec<Round>, // rounds of the enclosing groups, from the outermost to the innermost
+}
+
+impl Step {
+    /// The round of the innermost enclosing group
+    pub fn round(&self) -> Option<&Round> {
+        self.rounds.last()
+    }
+}
+
+/// A running interval training program
+#[derive(Debug, Clone)]
+pub struct Sequence<C = SystemClock> {
+    pub steps: Vec<Step>,
+    pub index: usize,    // index of the current step; `steps.len()` once finished
+    pub timer: Timer<C>, // the timer of the current step
+    pub log: Vec<(Step, TimerData)>, // finished steps
+}
+
+impl Sequence {
+    /// Returns a sequence paused at the beginning of the first interval of `program`
+    pub fn new(program: Vec<Segment>) -> Self {
+        Self::with_clock(program, SystemClock)
+    }
+}
+
+impl<C: ClockSource + Clone> Sequence<C> {
+    /// `Sequence::new(program)` on another [clock source](../clock/index.html)
+    pub fn with_clock(program: Vec<Segment>, clock: C) -> Self {
+        let mut steps = Vec::new();
+        for segment in &program {
+            segment.flatten(&mut Vec::new(), &mut steps);
+        }
+        let duration = steps.first().map_or(Duration::zero(), |step| step.duration);
+        Self {
+            steps,
+            index: 0,
+            timer: Timer::with_clock(duration, clock),
+            log: Vec::new(),
+        }
+    }
+    /// The current interval, or `None` once finished
+    pub fn current(&self) -> Option<&Step> {
+        self.steps.get(self.index)
+    }
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.steps.len()
+    }
+    /// Read the time remaining in the current interval. Does not advance to the next interval;
+    /// call `.update()` for that.
+    pub fn read(&self) -> Duration {
+        self.timer.read()
+    }
+
+    pub fn read_at(&self, moment: DateTime<Local>) -> Duration {
+        self.timer.read_at(moment)
+    }
+    /// Read the time remaining in the whole program
+    pub fn read_total(&self) -> Duration {
+        self.read_total_at(self.timer.now())
+    }
+
+    pub fn read_total_at(&self, moment: DateTime<Local>) -> Duration {
+        self.steps
+            .iter()
+            .skip(self.index + 1)
+            .fold(self.read_at(moment), |total, step| total + step.duration)
+    }
+    /// Advance past every interval that has expired. Returns the intervals completed, in order.
+    pub fn update(&mut self) -> Vec<Step> {
+        self.update_at(self.timer.now())
+    }
+
+    pub fn update_at(&mut self, moment: DateTime<Local>) -> Vec<Step> {
+        let mut completed = Vec::new();
+        while !self.is_finished() && self.timer.is_expired_at(moment) {
+            let expiry = self.timer.expires_at().unwrap_or(moment);
+            completed.push(self.steps[self.index].clone());
+            self.advance_at(expiry, true);
+        }
+        completed
+    }
+    /// Pause or resume the sequence.
+    pub fn pause_or_resume(&mut self) {
+        if self.timer.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    pub fn pause_or_resume_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        if !self.is_finished() {
+            self.timer.pause_or_resume_at(moment);
+        }
+    }
+    /// Pause the sequence (suggest using `pause_or_resume` instead.)
+    pub fn pause(&mut self) {
+        self.pause_at(self.timer.now());
+    }
+
+    pub fn pause_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        self.timer.pause_at(moment);
+    }
+    /// Resume the sequence (suggest using `pause_or_resume` instead.)
+    pub fn resume(&mut self) {
+        self.update();
+        if !self.is_finished() {
+            self.timer.resume();
+        }
+    }
+
+    pub fn resume_at(&mut self, moment: DateTime<Local>) {
+        self.update_at(moment);
+        if !self.is_finished() {
+            self.timer.resume_at(moment);
+        }
+    }
+    /// End the current interval early and begin the next one, which starts running if the
+    /// current interval was running. Returns the interval skipped, or `None` if finished.
+    pub fn skip(&mut self) -> Option<Step> {
+        self.skip_at(self.timer.now())
+    }
+
+    pub fn skip_at(&mut self, moment: DateTime<Local>) -> Option<Step> {
+        self.update_at(moment);
+        let skipped = self.current()?.clone();
+        self.advance_at(moment, !self.timer.paused);
+        Some(skipped)
+    }
+
+    /// Finish the current interval at `moment` and begin the next one
+    fn advance_at(&mut self, moment: DateTime<Local>, start: bool) {
+        let step = self.steps[self.index].clone();
+        self.index += 1;
+        let duration = self
+            .current()
+            .map_or(Duration::zero(), |step| step.duration);
+        let start = start && !self.is_finished();
+        let data = self.timer.begin_next_at(moment, duration, start);
+        if !data.start_moments.is_empty() {
+            self.log.push((step, data));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn intervals_ignore_wall_clock_jumps() {
+        let clock = ManualClock::new(Local::now());
+        let program = vec![Segment::group(
+            "set",
+            2,
+            vec![
+                Segment::interval("work", Duration::seconds(20)),
+                Segment::interval("rest", Duration::seconds(10)),
+            ],
+        )];
+        let mut sequence = Sequence::with_clock(program, clock.clone());
+        sequence.resume();
+        clock.advance(Duration::seconds(25));
+        clock.set(clock.now() + Duration::hours(1));
+        assert_eq!(sequence.update().len(), 1);
+        assert_eq!(sequence.read(), Duration::seconds(5));
+        assert_eq!(sequence.read_total(), Duration::seconds(35));
+    }
+}
@@ -0,0 +1,113 @@
+//! (De)serialisation helpers for the `serde` feature. Durations are (de)serialised as
+//! integer milliseconds; moments use chrono's RFC 3339 representation.
+
+pub(crate) mod duration {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(duration.num_milliseconds())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        i64::deserialize(d).map(Duration::milliseconds)
+    }
+}
+
+pub(crate) mod durations {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        durations: &[Duration],
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.collect_seq(durations.iter().map(Duration::num_milliseconds))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Duration>, D::Error> {
+        Vec::<i64>::deserialize(d).map(|v| v.into_iter().map(Duration::milliseconds).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stopwatch::{Stopwatch, StopwatchData};
+    use crate::timer::{Timer, TimerData, Warning};
+    use chrono::{Duration, Local};
+    use serde::{de::DeserializeOwned, Serialize};
+
+    fn minutes(minutes: i64) -> Duration {
+        Duration::minutes(minutes)
+    }
+
+    /// `value` after a round trip, checking that it serialises the same again
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        let restored: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        restored
+    }
+
+    #[test]
+    fn stopwatch_data() {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.resume_at(start);
+        stopwatch.lap_at(start + minutes(1));
+        stopwatch.split_at(start + minutes(2));
+        let data: StopwatchData = round_trip(&stopwatch.stop_at(start + minutes(3)));
+        assert_eq!(data.elapsed, minutes(3));
+        assert_eq!(data.laps, vec![minutes(1), minutes(2)]);
+        assert_eq!(data.start_moments, vec![start]);
+        assert_eq!(data.readings.len(), 3);
+    }
+
+    #[test]
+    fn running_stopwatch_keeps_counting() {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.resume_at(start);
+        stopwatch.lap_at(start + minutes(1));
+        let mut restored: Stopwatch = round_trip(&stopwatch);
+        assert!(!restored.paused);
+        assert_eq!(restored.read_at(start + minutes(5)), minutes(5));
+        assert_eq!(restored.lap_at(start + minutes(5)), Some(minutes(4)));
+        restored.pause_at(start + minutes(6));
+        assert_eq!(restored.read_at(start + minutes(9)), minutes(6));
+    }
+
+    #[test]
+    fn timer_data() {
+        let start = Local::now();
+        let mut timer = Timer::new(minutes(10));
+        timer.resume_at(start);
+        timer.add_time_at(start + minutes(2), minutes(5));
+        let data: TimerData = round_trip(&timer.stop_at(start + minutes(4)));
+        assert_eq!(data.total, minutes(15));
+        assert_eq!(data.remaining, minutes(11));
+        assert_eq!(data.adjustments.len(), 1);
+        assert_eq!(data.pause_moments, vec![start + minutes(4)]);
+    }
+
+    #[test]
+    fn running_timer_keeps_counting() {
+        let start = Local::now();
+        let mut timer = Timer::new(minutes(10));
+        timer.warnings.push(Warning::Remaining(minutes(5)));
+        timer.warnings.push(Warning::Every(minutes(3)));
+        timer.resume_at(start);
+        assert_eq!(timer.due_warnings_at(start + minutes(4)).len(), 1);
+        let mut restored: Timer = round_trip(&timer);
+        assert_eq!(restored.read_at(start + minutes(6)), minutes(4));
+        assert_eq!(restored.expires_at(), Some(start + minutes(10)));
+        // warnings already returned are not returned again
+        let due = restored.due_warnings_at(start + minutes(6));
+        assert_eq!(
+            due.iter()
+                .map(|warning| warning.warning)
+                .collect::<Vec<_>>(),
+            vec![Warning::Remaining(minutes(5)), Warning::Every(minutes(3))]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! # Shared
+//!
+//! Handles to a [`Stopwatch`](../stopwatch/struct.Stopwatch.html) or
+//! [`Timer`](../timer/struct.Timer.html) that can be cloned and used from many threads at once,
+//! e.g. controlled from a hotkey thread and rendered from a UI thread.
+//!
+//! ## Usage
+//!
+//! - Use `SharedStopwatch::new()` or `SharedTimer::new(<duration>)` to initialise, or convert an
+//!   existing stopwatch or timer with `.into()`. Clone the handle for each thread.
+//! - Call `.read()` as often as you like: it does not wait for (or hold up) the operations of
+//!   other threads, since it reads from a snapshot taken after the last operation.
+//! - Each operation (`.lap()`, `.pause_or_resume()`, `.stop()`, ...) is atomic. For anything
+//!   else, or several operations at once, call `.with(|<stopwatch or timer>| ...)`.
+//! - With the `tokio` feature, `.ticks(<interval>)` and `SharedTimer::expired()` hold a clone
+//!   of the handle rather than a borrow, so that a UI loop can `select!` on them and still
+//!   operate the stopwatch or timer (see [`tick`](../tick/index.html)).
+
+use crate::clock::{Anchor, ClockSource, SystemClock};
+use crate::stopwatch::{Stopwatch, StopwatchData};
+#[cfg(feature = "tokio")]
+use crate::tick::{until_boundary, Ticks};
+use crate::timer::{Timer, TimerData, TimerState};
+use chrono::{DateTime, Duration, Local};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Enough of a stopwatch or timer to read it without locking it
+#[derive(Debug, Clone, Copy)]
+struct Snapshot {
+    anchor: Anchor,          // when the snapshot was taken
+    value: Duration,         // the reading then
+    rate: i32,               // 1 if counting up, -1 if counting down, 0 if paused
+    floor: Option<Duration>, // the reading does not go below this
+}
+
+impl Snapshot {
+    fn new<C: ClockSource>(moment: DateTime<Local>, value: Duration, rate: i32, clock: &C) -> Self {
+        Self {
+            anchor: Anchor::new(moment, clock),
+            value,
+            rate,
+            floor: None,
+        }
+    }
+    fn read<C: ClockSource>(&self, clock: &C) -> Duration {
+        self.floored(self.read_raw(clock))
+    }
+    /// The reading, ignoring the floor
+    fn read_raw<C: ClockSource>(&self, clock: &C) -> Duration {
+        self.value + self.anchor.elapsed(clock) * self.rate
+    }
+    fn floored(&self, value: Duration) -> Duration {
+        self.floor.map_or(value, |floor| value.max(floor))
+    }
+}
+
+#[derive(Debug)]
+struct Inner<T, C> {
+    value: Mutex<T>,
+    snapshot: RwLock<Snapshot>,
+    clock: C,
+}
+
+/// A stopwatch shared between threads
+#[derive(Debug)]
+pub struct SharedStopwatch<C = SystemClock>(Arc<Inner<Stopwatch<C>, C>>);
+
+impl<C> Clone for SharedStopwatch<C> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl Default for SharedStopwatch {
+    fn default() -> Self {
+        Self::from(Stopwatch::new())
+    }
+}
+
+impl SharedStopwatch {
+    /// Returns a stopwatch reset to zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C: ClockSource + Clone> From<Stopwatch<C>> for SharedStopwatch<C> {
+    fn from(stopwatch: Stopwatch<C>) -> Self {
+        let clock = stopwatch.clock().clone();
+        let snapshot = Self::snapshot(&stopwatch);
+        Self(Arc::new(Inner {
+            value: Mutex::new(stopwatch),
+            snapshot: RwLock::new(snapshot),
+            clock,
+        }))
+    }
+}
+
+impl<C: ClockSource + Clone> SharedStopwatch<C> {
+    /// `SharedStopwatch::new()` on another [clock source](../clock/index.html)
+    pub fn with_clock(clock: C) -> Self {
+        Self::from(Stopwatch::with_clock(clock))
+    }
+    /// Read the total time elapsed, without locking the stopwatch
+    pub fn read(&self) -> Duration {
+        self.0.snapshot.read().unwrap().read(&self.0.clock)
+    }
+    pub fn is_paused(&self) -> bool {
+        self.0.snapshot.read().unwrap().rate == 0
+    }
+    /// A stream of readings of the total time elapsed, the first at once and the following ones
+    /// as it reaches a multiple of `interval`. The stream holds a clone of this handle.
+    #[cfg(feature = "tokio")]
+    pub fn ticks(&self, interval: Duration) -> Ticks<'static>
+    where
+        C: Send + Sync + 'static,
+    {
+        let shared = self.clone();
+        Ticks::new(interval, move || {
+            let snapshot = *shared.0.snapshot.read().unwrap();
+            let elapsed = snapshot.read(&shared.0.clock);
+            let until = if snapshot.rate == 0 {
+                None
+            } else {
+                Some(until_boundary(elapsed, interval))
+            };
+            (elapsed, until)
+        })
+    }
+    /// Run `f` on the stopwatch while no other thread can use it
+    pub fn with<R>(&self, f: impl FnOnce(&mut Stopwatch<C>) -> R) -> R {
+        let mut stopwatch = self.0.value.lock().unwrap();
+        let result = f(&mut stopwatch);
+        *self.0.snapshot.write().unwrap() = Self::snapshot(&stopwatch);
+        result
+    }
+    /// A copy of the data recorded so far
+    pub fn data(&self) -> StopwatchData {
+        self.0.value.lock().unwrap().data.clone()
+    }
+    pub fn lap(&self) -> Option<Duration> {
+        self.with(Stopwatch::lap)
+    }
+    pub fn split(&self) -> Option<Duration> {
+        self.with(Stopwatch::split)
+    }
+    pub fn pause_or_resume(&self) {
+        self.with(Stopwatch::pause_or_resume)
+    }
+    pub fn pause(&self) {
+        self.with(Stopwatch::pause)
+    }
+    pub fn resume(&self) {
+        self.with(Stopwatch::resume)
+    }
+    /// Stop the stopwatch, return the data, and reset the stopwatch
+    pub fn stop(&self) -> StopwatchData {
+        self.with(Stopwatch::stop)
+    }
+
+    fn snapshot(stopwatch: &Stopwatch<C>) -> Snapshot {
+        let moment = stopwatch.now();
+        let rate = if stopwatch.paused { 0 } else { 1 };
+        Snapshot::new(moment, stopwatch.read_at(moment), rate, stopwatch.clock())
+    }
+}
+
+/// A timer shared between threads
+#[derive(Debug)]
+pub struct SharedTimer<C = SystemClock>(Arc<Inner<Timer<C>, C>>);
+
+impl<C> Clone for SharedTimer<C> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl SharedTimer {
+    /// Returns a timer set to `duration`
+    pub fn new(duration: Duration) -> Self {
+        Self::from(Timer::new(duration))
+    }
+}
+
+impl<C: ClockSource + Clone> From<Timer<C>> for SharedTimer<C> {
+    fn from(timer: Timer<C>) -> Self {
+        let clock = timer.clock().clone();
+        let snapshot = Self::snapshot(&timer);
+        Self(Arc::new(Inner {
+            value: Mutex::new(timer),
+            snapshot: RwLock::new(snapshot),
+            clock,
+        }))
+    }
+}
+
+impl<C: ClockSource + Clone> SharedTimer<C> {
+    /// `SharedTimer::new(duration)` on another [clock source](../clock/index.html)
+    pub fn with_clock(duration: Duration, clock: C) -> Self {
+        Self::from(Timer::with_clock(duration, clock))
+    }
+    /// Read the time remaining, without locking the timer
+    pub fn read(&self) -> Duration {
+        self.0.snapshot.read().unwrap().read(&self.0.clock)
+    }
+    pub fn is_paused(&self) -> bool {
+        self.0.snapshot.read().unwrap().rate == 0
+    }
+    /// A stream of readings, the first at once and the following ones as the remaining time
+    /// reaches a multiple of `interval`. The stream holds a clone of this handle.
+    #[cfg(feature = "tokio")]
+    pub fn ticks(&self, interval: Duration) -> Ticks<'static>
+    where
+        C: Send + Sync + 'static,
+    {
+        let shared = self.clone();
+        Ticks::new(interval, move || {
+            let snapshot = *shared.0.snapshot.read().unwrap();
+            let remaining = snapshot.read_raw(&shared.0.clock);
+            let until = if snapshot.rate == 0 {
+                None
+            } else {
+                Some(until_boundary(-remaining, interval))
+            };
+            (snapshot.floored(remaining), until)
+        })
+    }
+    /// Resolves at the moment the timer expires (at once if it already has), following the
+    /// expiry as the timer is paused, resumed or adjusted through any handle
+    #[cfg(feature = "tokio")]
+    pub fn expired(&self) -> impl std::future::Future<Output = DateTime<Local>> + Send + 'static {
+        self.0.value.lock().unwrap().expired()
+    }
+    /// Run `f` on the timer while no other thread can use it
+    pub fn with<R>(&self, f: impl FnOnce(&mut Timer<C>) -> R) -> R {
+        let mut timer = self.0.value.lock().unwrap();
+        let result = f(&mut timer);
+        *self.0.snapshot.write().unwrap() = Self::snapshot(&timer);
+        result
+    }
+    /// A copy of the data recorded so far
+    pub fn data(&self) -> TimerData {
+        self.0.value.lock().unwrap().data.clone()
+    }
+    pub fn state(&self) -> TimerState {
+        self.0.value.lock().unwrap().state()
+    }
+    /// Record the expiry (and notify subscribers) if the timer has expired since the last
+    /// update. Returns whether it has.
+    pub fn update(&self) -> bool {
+        self.with(Timer::update)
+    }
+    pub fn pause_or_resume(&self) {
+        self.with(Timer::pause_or_resume)
+    }
+    pub fn pause(&self) {
+        self.with(Timer::pause)
+    }
+    pub fn resume(&self) {
+        self.with(Timer::resume)
+    }
+    /// Stop the timer, return the data, and reset the timer with the previously set duration
+    pub fn stop(&self) -> TimerData {
+        self.with(Timer::stop)
+    }
+
+    fn snapshot(timer: &Timer<C>) -> Snapshot {
+        let moment = timer.now();
+        let rate = if timer.paused { 0 } else { -1 };
+        let mut snapshot = Snapshot::new(moment, timer.read_at(moment), rate, timer.clock());
+        if !timer.overtime {
+            snapshot.floor = Some(Duration::zero());
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::thread;
+
+    fn millis(millis: i64) -> Duration {
+        Duration::milliseconds(millis)
+    }
+
+    #[test]
+    fn stopwatch_from_many_threads() {
+        let clock = ManualClock::new(Local::now());
+        let stopwatch = SharedStopwatch::with_clock(clock.clone());
+        stopwatch.resume();
+        let ticking = {
+            let clock = clock.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    clock.advance(millis(1));
+                }
+            })
+        };
+        let reading = {
+            let stopwatch = stopwatch.clone();
+            thread::spawn(move || {
+                let mut last = Duration::zero();
+                for _ in 0..1000 {
+                    let reading = stopwatch.read();
+                    assert!(reading >= last && reading <= millis(1000));
+                    last = reading;
+                }
+            })
+        };
+        let operating: Vec<_> = (0..4)
+            .map(|_| {
+                let stopwatch = stopwatch.clone();
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        stopwatch.lap();
+                        stopwatch.pause_or_resume();
+                    }
+                })
+            })
+            .collect();
+        for thread in operating {
+            thread.join().unwrap();
+        }
+        ticking.join().unwrap();
+        reading.join().unwrap();
+
+        // an even number of toggles leaves it running, with none lost
+        assert!(!stopwatch.is_paused());
+        let data = stopwatch.data();
+        assert_eq!(data.start_moments.len(), 101);
+        assert_eq!(data.pause_moments.len(), 100);
+        assert_eq!(
+            stopwatch.read(),
+            stopwatch.with(|stopwatch| stopwatch.read())
+        );
+        let data = stopwatch.stop();
+        let laps = data
+            .laps
+            .iter()
+            .fold(Duration::zero(), |total, lap| total + *lap);
+        assert_eq!(laps, data.elapsed);
+        assert_eq!(stopwatch.read(), Duration::zero());
+    }
+
+    #[test]
+    fn timer_from_many_threads() {
+        let clock = ManualClock::new(Local::now());
+        let timer = SharedTimer::with_clock(Duration::seconds(1), clock.clone());
+        timer.resume();
+        let adding: Vec<_> = (0..4)
+            .map(|_| {
+                let (timer, clock) = (timer.clone(), clock.clone());
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        timer.with(|timer| timer.add_time(millis(10)));
+                        clock.advance(millis(1));
+                        assert!(timer.read() > Duration::zero());
+                    }
+                })
+            })
+            .collect();
+        for thread in adding {
+            thread.join().unwrap();
+        }
+        let data = timer.data();
+        assert_eq!(data.adjustments.len(), 100);
+        assert_eq!(data.total, Duration::seconds(2));
+        // 100 ms run
+        assert_eq!(timer.read(), millis(1900));
+        clock.advance(millis(1900));
+        assert_eq!(timer.read(), Duration::zero());
+        assert!(timer.update());
+        assert_eq!(timer.state(), TimerState::Expired);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn operate_while_ticking() {
+        let clock = ManualClock::new(Local::now());
+        let stopwatch = SharedStopwatch::with_clock(clock.clone());
+        stopwatch.resume();
+        let mut ticks = stopwatch.ticks(Duration::seconds(1));
+        assert_eq!(ticks.tick().await, Duration::zero());
+        clock.advance(Duration::milliseconds(1500));
+        assert_eq!(stopwatch.lap(), Some(Duration::milliseconds(1500)));
+        stopwatch.pause();
+        assert_eq!(ticks.tick().await, Duration::milliseconds(1500));
+        assert!(stopwatch.is_paused());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn timer_expiry_follows_adjustments() {
+        let clock = ManualClock::new(Local::now());
+        let timer = SharedTimer::with_clock(Duration::seconds(10), clock.clone());
+        timer.resume();
+        let expired = timer.expired();
+        let mut ticks = timer.ticks(Duration::seconds(1));
+        assert_eq!(ticks.tick().await, Duration::seconds(10));
+        let start = timer.data().start();
+        timer.with(|timer| timer.add_time(Duration::seconds(5)));
+        assert_eq!(expired.await, start + Duration::seconds(15));
+    }
+}
@@ -5,15 +5,30 @@
 //! ## Usage
 //!
 //! - Use `Stopwatch::new()` to initialise a new stopwatch instance. The stopwatch is paused
-//! at `00:00` and will **not** run until you call `.resume()` or `.pause_or_resume()`.
+//!   at `00:00` and will **not** run until you call `.resume()` or `.pause_or_resume()`.
 //! - While running:
 //!     - Call `.lap()` to record lap times.
+//!     - Call `.split()` to record the total time elapsed, without starting a new lap.
 //!     - Call `.pause_or_resume()`, `.pause()` or `.resume()` to pause or resume.
 //! - When you want to stop (reset), call `.stop()`, which resets the stopwatch and returns
 //!   [`StopwatchData`](struct.StopwatchData.html)
+//! - Call `.subscribe(<callback>)` to be notified of [`Event`](../event/enum.Event.html)s.
+//! - With the `tokio` feature, call `.ticks(<interval>)` for a stream of readings aligned to
+//!   display boundaries (see [`tick`](../tick/index.html)).
+//! - To fix a recorded session (e.g. a forgotten pause), call `.insert_pause()`,
+//!   `.move_start()`, `.move_pause()`, `.delete_lap()`, `.split_off()` or `.trim()` on its data.
+//! - Call `.undo()` to revert the last operation (e.g. an accidental `.stop()`), and `.redo()`
+//!   to re-apply it.
+//! - Operations that are not valid in the current state (e.g. pausing a paused stopwatch) are
+//!   ignored, and moments earlier than the last recorded event are clamped to it. Use the
+//!   `try_` variants (e.g. `.try_pause()`) to get a [`ClockError`](../error/enum.ClockError.html)
+//!   instead.
 //!
 //! ## Examples
 //!
+//! Laps and splits are recorded in order in
+//! [`StopwatchData::readings`](struct.StopwatchData.html#structfield.readings).
+//!
 //! ## Schematic
 //!
 //! ```ignore
@@ -23,17 +38,52 @@
 //!          pause           pause            pause(end)
 //! ```
 
+use crate::clock::{as_nanos, Anchor, ClockSource, SystemClock};
+use crate::error::ClockError;
+use crate::event::{Event, Subscribers};
+use crate::history::History;
+use crate::segment::{self, Span};
+#[cfg(feature = "tokio")]
+use crate::tick::{until_boundary, Ticks};
 use chrono::{DateTime, Duration, Local};
+use std::sync::mpsc::Sender;
 use std::{default::Default, mem};
 
-#[derive(Debug)]
+/// Whether a [`Reading`](struct.Reading.html) is a lap or a split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReadingKind {
+    /// Ends the current lap and starts a new one
+    Lap,
+    /// Reads the time without starting a new lap
+    Split,
+}
+
+/// A lap or split reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reading {
+    pub kind: ReadingKind,
+    pub moment: DateTime<Local>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub elapsed: Duration, // total time elapsed at `moment`
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub lap_elapsed: Duration, // time elapsed in the current lap at `moment`; the lap time for a lap
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The data returned by [`Stopwatch`](struct.Stopwatch.html) upon `.stop`ping (i.e. resetting)
 pub struct StopwatchData {
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
     pub elapsed: Duration,
     pub pause_moments: Vec<DateTime<Local>>, // moments at which the stopwatch is paused
     pub start_moments: Vec<DateTime<Local>>, // moments at which the stopwatch resumes
     pub lap_moments: Vec<DateTime<Local>>,   // moments at which a lap time is read
-    pub laps: Vec<Duration>,                 // lap times
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::durations"))]
+    pub laps: Vec<Duration>, // lap times
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub readings: Vec<Reading>, // laps and splits, in order
 }
 
 impl Default for StopwatchData {
@@ -44,6 +94,7 @@ impl Default for StopwatchData {
             pause_moments: Vec::new(),
             lap_moments: Vec::new(),
             laps: Vec::new(),
+            readings: Vec::new(),
         }
     }
 }
@@ -52,28 +103,370 @@ impl StopwatchData {
     fn new() -> Self {
         Self::default()
     }
+    /// The moment at which the stopwatch was first started
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stopwatch was never started. See `.try_start()`.
     pub fn start(&self) -> DateTime<Local> {
-        self.start_moments[0]
+        self.try_start().unwrap()
     }
+    /// The moment at which the stopwatch was last paused (i.e. stopped)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stopwatch was never paused. See `.try_stop()`.
     pub fn stop(&self) -> DateTime<Local> {
-        self.pause_moments[self.pause_moments.len() - 1]
+        self.try_stop().unwrap()
+    }
+    pub fn try_start(&self) -> Result<DateTime<Local>, ClockError> {
+        self.start_moments
+            .first()
+            .copied()
+            .ok_or(ClockError::NotStarted)
+    }
+    pub fn try_stop(&self) -> Result<DateTime<Local>, ClockError> {
+        self.pause_moments
+            .last()
+            .copied()
+            .ok_or(ClockError::NotStarted)
+    }
+    /// The periods between each start and the following pause
+    pub fn running_segments(&self) -> impl Iterator<Item = Span> + '_ {
+        segment::running(&self.start_moments, &self.pause_moments)
+    }
+    /// The gaps between each pause and the following start
+    pub fn paused_segments(&self) -> impl Iterator<Item = Span> + '_ {
+        segment::paused(&self.start_moments, &self.pause_moments)
+    }
+    /// The total time of the running segments
+    pub fn active_time(&self) -> Duration {
+        segment::total(self.running_segments())
+    }
+    /// The total time of the paused segments
+    pub fn paused_time(&self) -> Duration {
+        segment::total(self.paused_segments())
+    }
+    /// Check that the start and pause moments alternate in order
+    pub fn validate(&self) -> Result<(), ClockError> {
+        segment::validate(&self.start_moments, &self.pause_moments)
+    }
+    /// Split readings, in order
+    pub fn splits(&self) -> impl Iterator<Item = &Reading> {
+        self.readings
+            .iter()
+            .filter(|reading| reading.kind == ReadingKind::Split)
+    }
+    /// The moment of the last recorded event (start, pause, lap or split)
+    fn last_event(&self) -> Option<DateTime<Local>> {
+        [
+            self.start_moments.last(),
+            self.pause_moments.last(),
+            self.lap_moments.last(),
+            self.readings.last().map(|reading| &reading.moment),
+        ]
+        .iter()
+        .flatten()
+        .max()
+        .map(|moment| **moment)
     }
 }
 
+/// Lap statistics. Methods returning `Option` return `None` if there are no laps.
+impl StopwatchData {
+    /// The index and time of the fastest lap (the first one, if tied)
+    pub fn fastest_lap(&self) -> Option<(usize, Duration)> {
+        self.laps
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by(|(i, a), (j, b)| a.cmp(b).then(i.cmp(j)))
+    }
+    /// The index and time of the slowest lap (the first one, if tied)
+    pub fn slowest_lap(&self) -> Option<(usize, Duration)> {
+        self.laps
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(i, a), (j, b)| a.cmp(b).then(j.cmp(i)))
+    }
+    pub fn mean_lap(&self) -> Option<Duration> {
+        if self.laps.is_empty() {
+            return None;
+        }
+        let total = self
+            .laps
+            .iter()
+            .fold(Duration::zero(), |acc, lap| acc + *lap);
+        Some(total / self.laps.len() as i32)
+    }
+    pub fn median_lap(&self) -> Option<Duration> {
+        self.lap_percentile(50.0)
+    }
+    /// The (population) standard deviation of lap times
+    pub fn lap_std_dev(&self) -> Option<Duration> {
+        let mean = as_nanos(self.mean_lap()?) as f64;
+        let variance = self
+            .laps
+            .iter()
+            .map(|lap| (as_nanos(*lap) as f64 - mean).powi(2))
+            .sum::<f64>()
+            / self.laps.len() as f64;
+        Some(Duration::nanoseconds(variance.sqrt().round() as i64))
+    }
+    /// The `percentile`th (0 to 100) percentile of lap times, interpolating linearly between
+    /// the closest ranks
+    pub fn lap_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut laps = self.laps.clone();
+        laps.sort();
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * (laps.len() as f64 - 1.0);
+        let (lower, upper) = (
+            *laps.get(rank.floor() as usize)?,
+            laps[rank.ceil() as usize],
+        );
+        let fraction = rank - rank.floor();
+        Some(
+            lower
+                + Duration::nanoseconds((as_nanos(upper - lower) as f64 * fraction).round() as i64),
+        )
+    }
+    /// The total time elapsed at the end of each lap
+    pub fn cumulative_lap_times(&self) -> Vec<Duration> {
+        self.laps
+            .iter()
+            .scan(Duration::zero(), |total, lap| {
+                *total += *lap;
+                Some(*total)
+            })
+            .collect()
+    }
+    /// How much longer (or, if negative, shorter) each lap is than the mean lap time
+    pub fn lap_deltas(&self) -> Vec<Duration> {
+        match self.mean_lap() {
+            Some(mean) => self.laps.iter().map(|lap| *lap - mean).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Retroactive editing, e.g. of a session in which a pause was forgotten. `elapsed`, `laps` and
+/// `readings` are recomputed from the edited moments. An edit that would leave the moments out of
+/// order, or a lap or split outside the running segments, is rejected and changes nothing.
+impl StopwatchData {
+    /// Insert a pause from `pause` to `resume`, within a single running segment
+    pub fn insert_pause(
+        &mut self,
+        pause: DateTime<Local>,
+        resume: DateTime<Local>,
+    ) -> Result<(), ClockError> {
+        self.edit(|data| {
+            segment::insert_pause(
+                &mut data.start_moments,
+                &mut data.pause_moments,
+                pause,
+                resume,
+            )
+        })
+    }
+    /// Move the `index`th start to `moment`
+    pub fn move_start(&mut self, index: usize, moment: DateTime<Local>) -> Result<(), ClockError> {
+        self.edit(|data| {
+            let start = data
+                .start_moments
+                .get_mut(index)
+                .ok_or(ClockError::NoSuchEvent { index })?;
+            *start = moment;
+            Ok(())
+        })
+    }
+    /// Move the `index`th pause to `moment`
+    pub fn move_pause(&mut self, index: usize, moment: DateTime<Local>) -> Result<(), ClockError> {
+        self.edit(|data| {
+            let pause = data
+                .pause_moments
+                .get_mut(index)
+                .ok_or(ClockError::NoSuchEvent { index })?;
+            *pause = moment;
+            Ok(())
+        })
+    }
+    /// Delete the `index`th lap, merging it into the following one, or into the preceding one if
+    /// it is the last, so that the laps still add up. Returns its lap time.
+    pub fn delete_lap(&mut self, index: usize) -> Result<Duration, ClockError> {
+        let lap = *self
+            .laps
+            .get(index)
+            .ok_or(ClockError::NoSuchEvent { index })?;
+        // the end of a lap is the start of the following one, so remove the end of the one
+        // merged into the other
+        let end = if index + 1 == self.laps.len() {
+            index.checked_sub(1).ok_or(ClockError::OnlyLap)?
+        } else {
+            index
+        };
+        self.lap_moments.remove(end);
+        let reading = self
+            .readings
+            .iter()
+            .enumerate()
+            .filter(|(_, reading)| reading.kind == ReadingKind::Lap)
+            .nth(end)
+            .map(|(i, _)| i);
+        if let Some(i) = reading {
+            self.readings.remove(i);
+        }
+        self.recompute();
+        Ok(lap)
+    }
+    /// Split the session at `moment`, keeping what happened until then and returning the rest.
+    /// The lap running at `moment` ends there, as if the stopwatch had been stopped.
+    pub fn split_off(&mut self, moment: DateTime<Local>) -> StopwatchData {
+        let mut rest = self.clip(Some(moment), None);
+        *self = self.clip(None, Some(moment));
+        rest.recompute();
+        self.recompute();
+        rest.close_lap();
+        self.close_lap();
+        rest
+    }
+    /// Keep only what happened between `from` and `to`
+    pub fn trim(&mut self, from: DateTime<Local>, to: DateTime<Local>) -> Result<(), ClockError> {
+        if to < from {
+            return Err(ClockError::OutOfOrder {
+                moment: to,
+                last: from,
+            });
+        }
+        *self = self.clip(Some(from), Some(to));
+        self.recompute();
+        self.close_lap();
+        Ok(())
+    }
+
+    /// Apply `edit` to a copy, and keep the copy if it is still consistent
+    fn edit(
+        &mut self,
+        edit: impl FnOnce(&mut Self) -> Result<(), ClockError>,
+    ) -> Result<(), ClockError> {
+        let mut data = self.clone();
+        edit(&mut data)?;
+        data.validate()?;
+        let moments = data
+            .lap_moments
+            .iter()
+            .chain(data.readings.iter().map(|reading| &reading.moment));
+        for moment in moments {
+            if !segment::is_running_at(&data.start_moments, &data.pause_moments, *moment) {
+                return Err(ClockError::NotRunningAt { moment: *moment });
+            }
+        }
+        data.recompute();
+        *self = data;
+        Ok(())
+    }
+
+    /// What happened between `from` and `to` (either unbounded if `None`), not yet recomputed
+    fn clip(&self, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> StopwatchData {
+        let within = |moment: &DateTime<Local>| {
+            from.is_none_or(|from| from < *moment) && to.is_none_or(|to| *moment <= to)
+        };
+        let (start_moments, pause_moments) =
+            segment::clip(&self.start_moments, &self.pause_moments, from, to);
+        StopwatchData {
+            start_moments,
+            pause_moments,
+            lap_moments: self.lap_moments.iter().copied().filter(within).collect(),
+            readings: self
+                .readings
+                .iter()
+                .copied()
+                .filter(|reading| within(&reading.moment))
+                .collect(),
+            ..StopwatchData::new()
+        }
+    }
+
+    /// End the lap still running at the last pause, as stopping does, so that the laps add up
+    /// to `elapsed` again
+    fn close_lap(&mut self) {
+        let (starts, pauses) = (&self.start_moments, &self.pause_moments);
+        let (first, end) = match (starts.first(), pauses.last()) {
+            (Some(first), Some(end)) => (*first, *end),
+            _ => return,
+        };
+        let lap_start = self.lap_moments.last().copied().unwrap_or(first);
+        let lap = segment::active_between(starts, pauses, lap_start, end);
+        if lap > Duration::zero() {
+            self.lap_moments.push(end);
+            self.laps.push(lap);
+            self.readings.push(Reading {
+                kind: ReadingKind::Lap,
+                moment: end,
+                elapsed: self.elapsed,
+                lap_elapsed: lap,
+            });
+        }
+    }
+
+    /// Recompute `elapsed`, `laps` and the readings from the moments
+    fn recompute(&mut self) {
+        let (starts, pauses) = (&self.start_moments, &self.pause_moments);
+        let first = match starts.first() {
+            Some(first) => *first,
+            None => return,
+        };
+        self.elapsed = segment::total(segment::running(starts, pauses));
+        let mut lap_start = first;
+        self.laps = self
+            .lap_moments
+            .iter()
+            .map(|moment| {
+                let lap = segment::active_between(starts, pauses, lap_start, *moment);
+                lap_start = *moment;
+                lap
+            })
+            .collect();
+        let mut lap_start = first;
+        for reading in &mut self.readings {
+            reading.elapsed = segment::active_between(starts, pauses, first, reading.moment);
+            reading.lap_elapsed =
+                segment::active_between(starts, pauses, lap_start, reading.moment);
+            if reading.kind == ReadingKind::Lap {
+                lap_start = reading.moment;
+            }
+        }
+    }
+}
+
+/// The state of a stopwatch before an operation, for undoing it
+#[derive(Debug, Clone)]
+struct Snapshot {
+    lap_elapsed: Duration,
+    paused: bool,
+    data: StopwatchData,
+    anchor: Option<Anchor>,
+}
+
 #[derive(Debug)]
-pub struct Stopwatch {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stopwatch<C = SystemClock> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
     pub lap_elapsed: Duration, // elapsed time of the current lap
     pub paused: bool,
     pub data: StopwatchData,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: C,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    anchor: Option<Anchor>, // set while running after a `.resume()` through the clock source
+    #[cfg_attr(feature = "serde", serde(skip))]
+    subscribers: Subscribers,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: History<Snapshot>,
 }
 
-impl Default for Stopwatch {
+impl<C: ClockSource + Default> Default for Stopwatch<C> {
     fn default() -> Self {
-        Self {
-            lap_elapsed: Duration::zero(),
-            paused: true, // stopped by default; start by explicitly calling `.resume()`
-            data: StopwatchData::new(),
-        }
+        Self::with_clock(C::default())
     }
 }
 
@@ -84,17 +477,97 @@ impl Stopwatch {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+impl<C: ClockSource> Stopwatch<C> {
+    /// `Stopwatch::new()` on another [clock source](../clock/index.html)
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            lap_elapsed: Duration::zero(),
+            paused: true, // stopped by default; start by explicitly calling `.resume()`
+            data: StopwatchData::new(),
+            clock,
+            anchor: None,
+            subscribers: Subscribers::default(),
+            history: History::default(),
+        }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// Revert the last lap, split, pause, resume or stop, restoring the data as it was before.
+    /// Returns whether there was one to revert.
+    pub fn undo(&mut self) -> bool {
+        if !self.history.can_undo() {
+            return false;
+        }
+        let snapshot = self.history.undo(self.snapshot());
+        snapshot.map(|snapshot| self.restore(snapshot)).is_some()
+    }
+    /// Re-apply the last operation undone. Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        if !self.history.can_redo() {
+            return false;
+        }
+        let snapshot = self.history.redo(self.snapshot());
+        snapshot.map(|snapshot| self.restore(snapshot)).is_some()
+    }
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+    /// Keep at most `limit` operations to undo (32 by default)
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+    /// Call `callback` with each [`Event`](../event/enum.Event.html) from now on
+    pub fn subscribe(&mut self, callback: impl Fn(&Event) + Send + Sync + 'static) {
+        self.subscribers.subscribe(callback);
+    }
+    /// Send each [`Event`](../event/enum.Event.html) from now on through `sender`
+    pub fn subscribe_channel(&mut self, sender: Sender<Event>) {
+        self.subscribers.subscribe_channel(sender);
+    }
+    /// A stream of readings of the total time elapsed, the first at once and the following ones
+    /// as it reaches a multiple of `interval`. The stream borrows the stopwatch; to operate it
+    /// while ticking, use `SharedStopwatch::ticks()`.
+    #[cfg(feature = "tokio")]
+    pub fn ticks(&self, interval: Duration) -> Ticks<'_>
+    where
+        C: Sync,
+    {
+        Ticks::new(interval, move || {
+            let elapsed = self.read();
+            let until = if self.paused {
+                None
+            } else {
+                Some(until_boundary(elapsed, interval))
+            };
+            (elapsed, until)
+        })
+    }
     /// Read the total time elapsed
     pub fn read(&self) -> Duration {
+        self.read_at(self.now())
+    }
+    /// Read the total time elapsed at `moment`
+    pub fn read_at(&self, moment: DateTime<Local>) -> Duration {
         if self.paused {
             self.data.elapsed
         } else {
-            self.data.elapsed + (Local::now() - self.last_start())
+            self.data.elapsed + (moment - self.last_start())
         }
     }
     /// Pause or resume the timer.
     pub fn pause_or_resume(&mut self) {
-        self.pause_or_resume_at(Local::now());
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
     }
 
     pub fn pause_or_resume_at(&mut self, moment: DateTime<Local>) {
@@ -107,40 +580,119 @@ impl Stopwatch {
     /// Lap the stopwatch. If the stopwatch is running, return `Some(<laptime>)`.
     /// If the stopwatch is paused, return `None`.
     pub fn lap(&mut self) -> Option<Duration> {
-        self.lap_at(Local::now())
+        self.lap_at(self.now())
     }
 
     pub fn lap_at(&mut self, moment: DateTime<Local>) -> Option<Duration> {
-        // assert!(!self.paused, "Paused!");
+        self.try_lap_at(self.clamp(moment)).ok()
+    }
+
+    /// Lap the stopwatch, or return an error if it is paused.
+    pub fn try_lap(&mut self) -> Result<Duration, ClockError> {
+        self.try_lap_at(self.now())
+    }
+
+    pub fn try_lap_at(&mut self, moment: DateTime<Local>) -> Result<Duration, ClockError> {
         if self.paused {
-            None
-        } else {
-            let lap = self.read_lap_elapsed(moment);
-            self.data.lap_moments.push(moment);
-            self.data.laps.push(lap);
-            self.lap_elapsed = Duration::zero();
-            Some(lap)
+            return Err(ClockError::NotRunning);
         }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
+        let lap = self.read_lap_elapsed(moment);
+        self.record_lap(moment, self.read_at(moment), lap);
+        self.subscribers.emit(Event::Lapped { moment, lap });
+        Ok(lap)
+    }
+
+    /// Record a split: the total time elapsed, without starting a new lap. If the stopwatch is
+    /// running, return `Some(<total time elapsed>)`. If the stopwatch is paused, return `None`.
+    pub fn split(&mut self) -> Option<Duration> {
+        self.split_at(self.now())
+    }
+
+    pub fn split_at(&mut self, moment: DateTime<Local>) -> Option<Duration> {
+        self.try_split_at(self.clamp(moment)).ok()
+    }
+
+    /// Record a split, or return an error if the stopwatch is paused.
+    pub fn try_split(&mut self) -> Result<Duration, ClockError> {
+        self.try_split_at(self.now())
+    }
+
+    pub fn try_split_at(&mut self, moment: DateTime<Local>) -> Result<Duration, ClockError> {
+        if self.paused {
+            return Err(ClockError::NotRunning);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
+        let elapsed = self.read_at(moment);
+        self.data.readings.push(Reading {
+            kind: ReadingKind::Split,
+            moment,
+            elapsed,
+            lap_elapsed: self.read_lap_elapsed(moment),
+        });
+        self.subscribers.emit(Event::Split { moment, elapsed });
+        Ok(elapsed)
     }
 
     /// resets the stopwatch and returns [`StopwatchData`](struct.StopwatchData.html)
     pub fn stop(&mut self) -> StopwatchData {
-        self.stop_at(Local::now())
+        self.stop_at(self.now())
     }
 
     pub fn stop_at(&mut self, moment: DateTime<Local>) -> StopwatchData {
-        // lap
-        let lap = self.read_lap_elapsed(moment);
-        self.data.lap_moments.push(moment);
-        self.data.laps.push(lap);
+        // a stopwatch that was never started has nothing to return
+        self.try_stop_at(self.clamp(moment)).unwrap_or_default()
+    }
+
+    /// resets the stopwatch and returns [`StopwatchData`](struct.StopwatchData.html), or
+    /// returns an error if it was never started.
+    pub fn try_stop(&mut self) -> Result<StopwatchData, ClockError> {
+        self.try_stop_at(self.now())
+    }
+
+    pub fn try_stop_at(&mut self, moment: DateTime<Local>) -> Result<StopwatchData, ClockError> {
+        if self.data.start_moments.is_empty() {
+            return Err(ClockError::NotStarted);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
+        if self.paused {
+            // the current lap ended when the stopwatch was paused
+            if self.lap_elapsed > Duration::zero() {
+                self.record_lap(self.data.stop(), self.data.elapsed, self.lap_elapsed);
+            }
+        } else {
+            // lap
+            let lap = self.read_lap_elapsed(moment);
+            self.record_lap(moment, self.read_at(moment), lap);
+            // pause
+            self.data.pause_moments.push(moment);
+            self.data.elapsed += moment - self.last_start();
+            self.paused = true;
+        }
         self.lap_elapsed = Duration::zero();
-        // pause
-        self.data.pause_moments.push(moment);
-        self.data.elapsed = self.data.elapsed + (moment - self.last_start());
-        self.paused = true;
+        self.anchor = None;
+        self.subscribers.emit(Event::Stopped { moment });
         // data
-        let data = mem::replace(&mut self.data, StopwatchData::new());
-        data
+        Ok(mem::replace(&mut self.data, StopwatchData::new()))
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lap_elapsed: self.lap_elapsed,
+            paused: self.paused,
+            data: self.data.clone(),
+            anchor: self.anchor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lap_elapsed = snapshot.lap_elapsed;
+        self.paused = snapshot.paused;
+        self.data = snapshot.data;
+        self.anchor = snapshot.anchor;
     }
 
     /// Read the time elapsed in the current lap
@@ -153,6 +705,18 @@ impl Stopwatch {
             }
     }
 
+    fn record_lap(&mut self, moment: DateTime<Local>, elapsed: Duration, lap: Duration) {
+        self.data.lap_moments.push(moment);
+        self.data.laps.push(lap);
+        self.data.readings.push(Reading {
+            kind: ReadingKind::Lap,
+            moment,
+            elapsed,
+            lap_elapsed: lap,
+        });
+        self.lap_elapsed = Duration::zero();
+    }
+
     fn last_start(&self) -> DateTime<Local> {
         self.data.start_moments[self.data.start_moments.len() - 1]
     }
@@ -161,22 +725,379 @@ impl Stopwatch {
     }
     /// Pause the stopwatch (suggest using `pause_or_resume` instead.)
     pub fn pause(&mut self) {
-        self.pause_at(Local::now());
+        self.pause_at(self.now());
     }
     /// Resume the stopwatch (suggest using `pause_or_resume` instead.)
     pub fn resume(&mut self) {
-        self.resume_at(Local::now());
+        let moment = self.clamp(self.clock.now());
+        if self.try_resume_at(moment).is_ok() {
+            self.anchor = Some(Anchor::new(moment, &self.clock));
+        }
     }
 
     pub fn pause_at(&mut self, moment: DateTime<Local>) {
+        self.try_pause_at(self.clamp(moment)).ok();
+    }
+
+    pub fn resume_at(&mut self, moment: DateTime<Local>) {
+        self.try_resume_at(self.clamp(moment)).ok();
+    }
+
+    /// Pause the stopwatch, or return an error if it is already paused.
+    pub fn try_pause(&mut self) -> Result<(), ClockError> {
+        self.try_pause_at(self.now())
+    }
+    /// Resume the stopwatch, or return an error if it is already running.
+    pub fn try_resume(&mut self) -> Result<(), ClockError> {
+        let moment = self.clock.now();
+        self.try_resume_at(moment)?;
+        self.anchor = Some(Anchor::new(moment, &self.clock));
+        Ok(())
+    }
+
+    pub fn try_pause_at(&mut self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        if self.paused {
+            return Err(ClockError::NotRunning);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
         self.data.pause_moments.push(moment);
-        self.data.elapsed = self.data.elapsed + (moment - self.last_start());
+        self.data.elapsed += moment - self.last_start();
         self.lap_elapsed = self.read_lap_elapsed(moment);
         self.paused = true;
+        self.subscribers.emit(Event::Paused { moment });
+        Ok(())
     }
 
-    pub fn resume_at(&mut self, moment: DateTime<Local>) {
+    pub fn try_resume_at(&mut self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        if !self.paused {
+            return Err(ClockError::AlreadyRunning);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
         self.data.start_moments.push(moment);
         self.paused = false;
+        self.anchor = None;
+        self.subscribers
+            .emit(if self.data.start_moments.len() == 1 {
+                Event::Started { moment }
+            } else {
+                Event::Resumed { moment }
+            });
+        Ok(())
+    }
+
+    /// The current moment. While running after a `.resume()`, this is measured on the monotonic
+    /// clock from the resume moment, so that wall-clock jumps do not affect elapsed time.
+    pub(crate) fn now(&self) -> DateTime<Local> {
+        match self.anchor {
+            Some(anchor) if !self.paused => anchor.now(&self.clock),
+            _ => self.clock.now(),
+        }
+    }
+
+    fn check_order(&self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        match self.data.last_event() {
+            Some(last) if moment < last => Err(ClockError::OutOfOrder { moment, last }),
+            _ => Ok(()),
+        }
+    }
+
+    /// `moment`, or the moment of the last recorded event if `moment` is earlier
+    fn clamp(&self, moment: DateTime<Local>) -> DateTime<Local> {
+        match self.data.last_event() {
+            Some(last) if moment < last => last,
+            _ => moment,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: i64) -> Duration {
+        Duration::minutes(minutes)
+    }
+
+    fn seconds(seconds: i64) -> Duration {
+        Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn undo_stop() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(Local::now());
+        let mut stopwatch = Stopwatch::with_clock(clock.clone());
+        stopwatch.resume();
+        clock.advance(seconds(5));
+        let data = stopwatch.stop();
+        assert_eq!(data.elapsed, seconds(5));
+        assert!(stopwatch.undo());
+        assert!(!stopwatch.paused);
+        // running on the monotonic clock again, whatever the wall clock does
+        clock.set(clock.now() - minutes(30));
+        clock.advance(seconds(2));
+        assert_eq!(stopwatch.read(), seconds(7));
+
+        assert!(stopwatch.redo());
+        assert!(stopwatch.paused);
+        assert_eq!(stopwatch.read(), Duration::zero());
+        assert!(!stopwatch.redo());
+    }
+
+    #[test]
+    fn new_operation_clears_redo() {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.resume_at(start);
+        stopwatch.lap_at(start + minutes(1));
+        stopwatch.lap_at(start + minutes(2));
+        assert!(stopwatch.undo());
+        assert_eq!(stopwatch.data.laps, vec![minutes(1)]);
+        assert!(stopwatch.can_redo());
+        stopwatch.split_at(start + minutes(3));
+        assert!(!stopwatch.redo());
+        assert_eq!(stopwatch.data.laps, vec![minutes(1)]);
+    }
+
+    #[test]
+    fn undo_limit() {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.set_undo_limit(2);
+        stopwatch.resume_at(start);
+        for lap in 1..=3 {
+            stopwatch.lap_at(start + minutes(lap));
+        }
+        assert!(stopwatch.undo());
+        assert!(stopwatch.undo());
+        assert!(!stopwatch.undo());
+        assert_eq!(stopwatch.data.laps, vec![minutes(1)]);
+    }
+
+    fn with_laps(laps: &[i64]) -> StopwatchData {
+        StopwatchData {
+            laps: laps.iter().map(|lap| seconds(*lap)).collect(),
+            ..StopwatchData::default()
+        }
+    }
+
+    #[test]
+    fn no_laps() {
+        let data = with_laps(&[]);
+        assert_eq!(data.fastest_lap(), None);
+        assert_eq!(data.slowest_lap(), None);
+        assert_eq!(data.mean_lap(), None);
+        assert_eq!(data.median_lap(), None);
+        assert_eq!(data.lap_std_dev(), None);
+        assert_eq!(data.lap_percentile(90.0), None);
+        assert!(data.cumulative_lap_times().is_empty());
+        assert!(data.lap_deltas().is_empty());
+    }
+
+    #[test]
+    fn fastest_and_slowest_ties() {
+        let data = with_laps(&[3, 1, 4, 1, 4]);
+        assert_eq!(data.fastest_lap(), Some((1, seconds(1))));
+        assert_eq!(data.slowest_lap(), Some((2, seconds(4))));
+    }
+
+    #[test]
+    fn mean_and_median() {
+        let data = with_laps(&[4, 1, 3, 2]);
+        assert_eq!(data.mean_lap(), Some(Duration::milliseconds(2500)));
+        assert_eq!(data.median_lap(), Some(Duration::milliseconds(2500)));
+        assert_eq!(with_laps(&[5, 1, 3]).median_lap(), Some(seconds(3)));
+        assert_eq!(with_laps(&[7]).median_lap(), Some(seconds(7)));
+    }
+
+    #[test]
+    fn percentiles() {
+        let data = with_laps(&[50, 10, 40, 20, 30]);
+        assert_eq!(data.lap_percentile(0.0), Some(seconds(10)));
+        assert_eq!(data.lap_percentile(25.0), Some(seconds(20)));
+        assert_eq!(data.lap_percentile(10.0), Some(seconds(14)));
+        assert_eq!(data.lap_percentile(90.0), Some(seconds(46)));
+        assert_eq!(data.lap_percentile(100.0), Some(seconds(50)));
+        assert_eq!(data.lap_percentile(150.0), Some(seconds(50)));
+    }
+
+    #[test]
+    fn std_dev_and_deltas() {
+        let data = with_laps(&[2, 4, 4, 4, 5, 5, 7, 9]);
+        assert_eq!(data.lap_std_dev(), Some(seconds(2)));
+        assert_eq!(with_laps(&[3, 3]).lap_std_dev(), Some(Duration::zero()));
+        let data = with_laps(&[1, 2, 6]);
+        assert_eq!(
+            data.cumulative_lap_times(),
+            vec![seconds(1), seconds(3), seconds(9)]
+        );
+        assert_eq!(
+            data.lap_deltas(),
+            vec![seconds(-2), seconds(-1), seconds(3)]
+        );
+    }
+
+    /// Laps of 1, 2 and 3 minutes, with a pause of 2 minutes in the last
+    fn session() -> (StopwatchData, DateTime<Local>) {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.resume_at(start);
+        stopwatch.lap_at(start + minutes(1));
+        stopwatch.lap_at(start + minutes(3));
+        stopwatch.pause_at(start + minutes(4));
+        stopwatch.resume_at(start + minutes(6));
+        (stopwatch.stop_at(start + minutes(8)), start)
+    }
+
+    fn assert_consistent(data: &StopwatchData) {
+        data.validate().unwrap();
+        let laps = data
+            .laps
+            .iter()
+            .fold(Duration::zero(), |total, lap| total + *lap);
+        assert_eq!(laps, data.elapsed);
+        assert_eq!(data.elapsed, data.active_time());
+    }
+
+    #[test]
+    fn read_with_manual_clock() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(Local::now());
+        let mut stopwatch = Stopwatch::with_clock(clock.clone());
+        stopwatch.resume();
+        clock.advance(seconds(5));
+        assert_eq!(stopwatch.lap(), Some(seconds(5)));
+        clock.set(clock.now() - minutes(30)); // the wall clock goes back
+        clock.advance(seconds(2));
+        assert_eq!(stopwatch.read(), seconds(7));
+        stopwatch.pause();
+        clock.advance(minutes(1));
+        assert_eq!(stopwatch.read(), seconds(7));
+    }
+
+    #[test]
+    fn insert_pause() {
+        let (mut data, start) = session();
+        assert_consistent(&data);
+        data.insert_pause(start + minutes(2), start + seconds(150))
+            .unwrap();
+        assert_eq!(data.elapsed, seconds(330));
+        assert_eq!(data.laps, vec![minutes(1), seconds(90), minutes(3)]);
+        assert_consistent(&data);
+
+        // across an existing pause
+        let before = data.clone();
+        assert_eq!(
+            data.insert_pause(start + minutes(3), start + minutes(7)),
+            Err(ClockError::NotRunningAt {
+                moment: start + minutes(3)
+            })
+        );
+        assert_eq!(data.laps, before.laps);
+        assert_eq!(data.start_moments, before.start_moments);
+    }
+
+    #[test]
+    fn move_start_and_pause() {
+        let (mut data, start) = session();
+        data.move_start(0, start + seconds(30)).unwrap();
+        assert_eq!(data.laps[0], seconds(30));
+        data.move_pause(0, start + minutes(5)).unwrap();
+        assert_eq!(data.elapsed, seconds(390));
+        assert_consistent(&data);
+
+        let before = data.clone();
+        assert_eq!(
+            data.move_pause(0, start + minutes(7)),
+            Err(ClockError::NotInterleaved { index: 2 })
+        );
+        // a lap would fall in the pause
+        assert_eq!(
+            data.move_pause(0, start + seconds(150)),
+            Err(ClockError::NotRunningAt {
+                moment: start + minutes(3)
+            })
+        );
+        assert_eq!(data.pause_moments, before.pause_moments);
+        assert_eq!(data.elapsed, before.elapsed);
+    }
+
+    #[test]
+    fn move_missing_events() {
+        let (mut data, start) = session();
+        assert_eq!(
+            data.move_start(2, start),
+            Err(ClockError::NoSuchEvent { index: 2 })
+        );
+        assert_eq!(
+            data.move_pause(5, start),
+            Err(ClockError::NoSuchEvent { index: 5 })
+        );
+        assert_eq!(
+            StopwatchData::default().move_start(3, start),
+            Err(ClockError::NoSuchEvent { index: 3 })
+        );
+    }
+
+    #[test]
+    fn delete_lap() {
+        let (mut data, _) = session();
+        assert_eq!(data.delete_lap(1), Ok(minutes(2)));
+        assert_eq!(data.laps, vec![minutes(1), minutes(5)]);
+        assert_consistent(&data);
+
+        let (mut data, _) = session();
+        assert_eq!(data.delete_lap(2), Ok(minutes(3)));
+        assert_eq!(data.laps, vec![minutes(1), minutes(5)]);
+        assert_eq!(data.readings.len(), 2);
+        assert_consistent(&data);
+
+        assert_eq!(
+            data.delete_lap(5),
+            Err(ClockError::NoSuchEvent { index: 5 })
+        );
+        assert_eq!(data.delete_lap(0), Ok(minutes(1)));
+        assert_eq!(data.delete_lap(0), Err(ClockError::OnlyLap));
+        assert_eq!(data.laps, vec![minutes(6)]);
+        assert_consistent(&data);
+    }
+
+    #[test]
+    fn split_off_and_trim() {
+        let (mut data, start) = session();
+        let rest = data.split_off(start + seconds(210));
+        assert_eq!(data.elapsed, seconds(210));
+        assert_eq!(data.laps, vec![minutes(1), minutes(2), seconds(30)]);
+        assert_eq!(data.lap_moments.last(), Some(&(start + seconds(210))));
+        assert_consistent(&data);
+        assert_eq!(rest.elapsed, seconds(150));
+        assert_eq!(rest.laps, vec![seconds(150)]);
+        assert_consistent(&rest);
+
+        // at a lap, nothing is left running
+        let (mut data, start) = session();
+        let rest = data.split_off(start + minutes(3));
+        assert_eq!(data.laps, vec![minutes(1), minutes(2)]);
+        assert_consistent(&data);
+        assert_consistent(&rest);
+
+        let (mut data, start) = session();
+        data.trim(start + minutes(2), start + minutes(7)).unwrap();
+        assert_eq!(
+            data.start_moments,
+            vec![start + minutes(2), start + minutes(6)]
+        );
+        assert_eq!(
+            data.pause_moments,
+            vec![start + minutes(4), start + minutes(7)]
+        );
+        assert_eq!(data.elapsed, minutes(3));
+        assert_eq!(data.laps, vec![minutes(1), minutes(2)]);
+        assert_consistent(&data);
+        assert!(data.trim(start + minutes(7), start).is_err());
     }
 }
@@ -0,0 +1,148 @@
+//! # Ticks
+//!
+//! Async readings of [`Stopwatch`](../stopwatch/struct.Stopwatch.html) and
+//! [`Timer`](../timer/struct.Timer.html) for UIs driven by a tokio runtime. Requires the `tokio`
+//! feature.
+//!
+//! ## Usage
+//!
+//! - Call `.ticks(<interval>)` on a stopwatch or timer for a [`Ticks`](struct.Ticks.html)
+//!   stream of readings. The first reading is yielded at once, and the following ones as the
+//!   reading crosses a multiple of `<interval>` (e.g. whole seconds or centiseconds), so that a
+//!   display redrawn on each tick changes exactly when it should. While paused, the reading is
+//!   repeated every `<interval>`.
+//! - Await `Timer::expired()` for the moment the timer expires. Pausing, resuming or extending
+//!   the timer while waiting moves the expiry accordingly.
+//! - A stream from a `Stopwatch` or `Timer` borrows it for as long as the stream lives. To
+//!   operate it while ticking (e.g. lap or pause from the same `select!` loop), use a
+//!   [`SharedStopwatch` or `SharedTimer`](../shared/index.html), whose streams hold a clone of
+//!   the handle instead.
+//! - Sleeping is measured on tokio's clock, whatever the
+//!   [`ClockSource`](../clock/trait.ClockSource.html) of the stopwatch or timer.
+
+use crate::clock::as_nanos;
+use chrono::{DateTime, Duration, Local};
+use futures_core::Stream;
+use std::{
+    convert::TryFrom,
+    fmt,
+    future::{pending, poll_fn, Future},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::{
+    sync::watch,
+    time::{sleep_until, Instant, Sleep},
+};
+
+/// A reading, and the time until the next one is due (`None` while paused)
+type Read<'a> = Box<dyn Fn() -> (Duration, Option<Duration>) + Send + Sync + 'a>;
+
+/// Readings of a stopwatch or timer, aligned to multiples of an interval
+pub struct Ticks<'a> {
+    read: Read<'a>,
+    interval: Duration,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<'a> Ticks<'a> {
+    pub(crate) fn new(
+        interval: Duration,
+        read: impl Fn() -> (Duration, Option<Duration>) + Send + Sync + 'a,
+    ) -> Self {
+        Self {
+            read: Box::new(read),
+            interval,
+            sleep: None,
+        }
+    }
+    /// Wait for the next reading
+    pub async fn tick(&mut self) -> Duration {
+        let mut ticks = Pin::new(self);
+        poll_fn(|cx| ticks.as_mut().poll_next(cx))
+            .await
+            .unwrap_or_else(Duration::zero)
+    }
+}
+
+impl Stream for Ticks<'_> {
+    type Item = Duration;
+
+    /// Never ends
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Duration>> {
+        let this = self.get_mut();
+        if let Some(sleep) = &mut this.sleep {
+            ready!(sleep.as_mut().poll(cx));
+        }
+        let (reading, until) = (this.read)();
+        let wait = until.unwrap_or(this.interval).to_std().unwrap_or_default();
+        let deadline = Instant::now() + wait;
+        match &mut this.sleep {
+            Some(sleep) => sleep.as_mut().reset(deadline),
+            None => this.sleep = Some(Box::pin(sleep_until(deadline))),
+        }
+        Poll::Ready(Some(reading))
+    }
+}
+
+impl fmt::Debug for Ticks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ticks")
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+/// The time until a reading counting up from `reading` next reaches a multiple of `interval`
+pub(crate) fn until_boundary(reading: Duration, interval: Duration) -> Duration {
+    let interval_nanos = as_nanos(interval).max(1);
+    let until = interval_nanos - as_nanos(reading).rem_euclid(interval_nanos);
+    Duration::nanoseconds(i64::try_from(until).unwrap_or(i64::MAX))
+}
+
+/// The expected expiry of a timer, watched by its `.expired()` futures
+#[derive(Debug)]
+pub(crate) struct Deadline(watch::Sender<Option<(Instant, DateTime<Local>)>>);
+
+impl Default for Deadline {
+    fn default() -> Self {
+        Self(watch::channel(None).0)
+    }
+}
+
+impl Clone for Deadline {
+    /// A clone is a separate timer, so its futures are not woken by this one
+    fn clone(&self) -> Self {
+        Self(watch::channel(*self.0.borrow()).0)
+    }
+}
+
+impl Deadline {
+    /// Move the expiry to `expiry`, or to never if `None`
+    pub(crate) fn set(&self, now: DateTime<Local>, expiry: Option<DateTime<Local>>) {
+        self.0.send_replace(expiry.map(|expiry| {
+            let until = (expiry - now).to_std().unwrap_or_default();
+            (Instant::now() + until, expiry)
+        }));
+    }
+    /// Resolves at the expiry, following it as it moves. Never resolves if the timer is dropped
+    /// before expiring.
+    pub(crate) fn expired(&self) -> impl Future<Output = DateTime<Local>> + Send + 'static {
+        let mut receiver = self.0.subscribe();
+        async move {
+            loop {
+                let deadline = *receiver.borrow_and_update();
+                let changed = match deadline {
+                    Some((instant, expiry)) => tokio::select! {
+                        _ = sleep_until(instant) => return expiry,
+                        changed = receiver.changed() => changed,
+                    },
+                    None => receiver.changed().await,
+                };
+                if changed.is_err() {
+                    return pending().await;
+                }
+            }
+        }
+    }
+}
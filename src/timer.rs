@@ -5,20 +5,89 @@
 //! ## Usage
 //!
 //! - Use `Timer::new(<duration>)` to initialise a new timer instance. `<duration>` is a
-//! `chrono::Duration`. The timer is paused at the duration you specified and will **not**
-//! run until you call `.resume()` or `.pause_or_resume()`.
+//!   `chrono::Duration`. The timer is paused at the duration you specified and will **not**
+//!   run until you call `.resume()` or `.pause_or_resume()`.
 //! - While running, call `.pause_or_resume()`, `.pause()` or `.resume()` to pause or resume.
 //! - When you want to stop (reset), call `.stop()`, which resets the timer and returns
 //!   [`TimerData`](struct.TimerData.html)
+//! - Call `.state()` to tell whether the timer is idle, running, paused or expired, and
+//!   `.expires_at()` for the moment at which it expires (or expired).
+//! - Once expired, `.read()` stays at zero. Set `.overtime` to `true` to have it count up into
+//!   overtime instead (as negative durations); the time spent past expiry is recorded in
+//!   `TimerData::overtime` either way.
+//! - Call `.add_time(<duration>)`, `.subtract_time(<duration>)`, `.restart()` or
+//!   `.set_total(<duration>)` to change the countdown without stopping it. Each change is
+//!   recorded in `TimerData::adjustments`.
+//! - Push [`Warning`](enum.Warning.html)s to `.warnings` (e.g. at 5 minutes left, or every 10
+//!   minutes) and call `.due_warnings()` regularly for those that have become due.
+//! - Call `.subscribe(<callback>)` to be notified of [`Event`](../event/enum.Event.html)s, and
+//!   `.update()` regularly to have warnings and expiry noticed as soon as they are due.
+//! - With the `tokio` feature, call `.ticks(<interval>)` for a stream of readings aligned to
+//!   display boundaries and `.expired()` for a future that resolves at expiry (see
+//!   [`tick`](../tick/index.html)).
+//! - To fix a recorded session (e.g. a forgotten pause), call `.insert_pause()`,
+//!   `.move_start()`, `.move_pause()`, `.split_off()` or `.trim()` on its data.
+//! - Call `.undo()` to revert the last operation (e.g. an accidental `.stop()`), and `.redo()`
+//!   to re-apply it.
+//! - Operations that are not valid in the current state (e.g. resuming a running timer) are
+//!   ignored, and moments earlier than the last recorded event are clamped to it. Use the
+//!   `try_` variants (e.g. `.try_resume()`) to get a [`ClockError`](../error/enum.ClockError.html)
+//!   instead.
 
+use crate::clock::{as_nanos, Anchor, ClockSource, SystemClock};
+use crate::error::ClockError;
+use crate::event::{Event, Subscribers};
+use crate::history::History;
+use crate::segment::{self, Span};
+#[cfg(feature = "tokio")]
+use crate::tick::{until_boundary, Deadline, Ticks};
 use chrono::{DateTime, Duration, Local};
+use std::sync::mpsc::Sender;
+
+/// What an [`Adjustment`](struct.Adjustment.html) did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdjustmentKind {
+    /// Time added, e.g. by a "+1 min" button
+    Add,
+    /// Time taken off
+    Subtract,
+    /// The countdown started over from the total
+    Restart,
+    /// The total changed, keeping the time already run
+    SetTotal,
+}
+
+/// A change made to the countdown of a timer after it was started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adjustment {
+    pub kind: AdjustmentKind,
+    pub moment: DateTime<Local>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub previous_total: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub total: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub previous_remaining: Duration, // negative in overtime
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub remaining: Duration,
+}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimerData {
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
     pub total: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
     pub remaining: Duration,
     pub start_moments: Vec<DateTime<Local>>, // moments at which the timer resumes; the first is the start monent
     pub pause_moments: Vec<DateTime<Local>>, // moments at which the timer is paused; the last is the stop moment
+    pub expired_at: Option<DateTime<Local>>, // moment at which the remaining time reached zero
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    pub overtime: Duration, // time run past expiry (since the last adjustment that added time)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub adjustments: Vec<Adjustment>, // in order
 }
 
 impl TimerData {
@@ -28,48 +97,666 @@ impl TimerData {
             remaining: duration,
             start_moments: Vec::new(),
             pause_moments: Vec::new(),
+            expired_at: None,
+            overtime: Duration::zero(),
+            adjustments: Vec::new(),
         }
     }
+    /// The moment at which the timer was first started
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer was never started. See `.try_start()`.
     pub fn start(&self) -> DateTime<Local> {
-        self.start_moments[0]
+        self.try_start().unwrap()
     }
+    /// The moment at which the timer was last paused (i.e. stopped)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer was never paused. See `.try_stop()`.
     pub fn stop(&self) -> DateTime<Local> {
-        self.pause_moments[self.pause_moments.len() - 1]
+        self.try_stop().unwrap()
+    }
+    pub fn try_start(&self) -> Result<DateTime<Local>, ClockError> {
+        self.start_moments
+            .first()
+            .copied()
+            .ok_or(ClockError::NotStarted)
+    }
+    pub fn try_stop(&self) -> Result<DateTime<Local>, ClockError> {
+        self.pause_moments
+            .last()
+            .copied()
+            .ok_or(ClockError::NotStarted)
+    }
+    /// The periods between each start and the following pause
+    pub fn running_segments(&self) -> impl Iterator<Item = Span> + '_ {
+        segment::running(&self.start_moments, &self.pause_moments)
+    }
+    /// The gaps between each pause and the following start
+    pub fn paused_segments(&self) -> impl Iterator<Item = Span> + '_ {
+        segment::paused(&self.start_moments, &self.pause_moments)
+    }
+    /// The total time of the running segments
+    pub fn active_time(&self) -> Duration {
+        segment::total(self.running_segments())
+    }
+    /// The total time of the paused segments
+    pub fn paused_time(&self) -> Duration {
+        segment::total(self.paused_segments())
+    }
+    /// Check that the start and pause moments alternate in order
+    pub fn validate(&self) -> Result<(), ClockError> {
+        segment::validate(&self.start_moments, &self.pause_moments)
     }
     pub fn duration_expected(&self) -> Duration {
         self.total
     }
+    /// The total before any adjustment
+    pub fn original_total(&self) -> Duration {
+        self.adjustments
+            .first()
+            .map_or(self.total, |adjustment| adjustment.previous_total)
+    }
+    /// # Panics
+    ///
+    /// Panics if the timer was never started and stopped. See `.try_duration_actual()`.
     pub fn duration_actual(&self) -> Duration {
-        self.stop() - self.start()
+        self.try_duration_actual().unwrap()
+    }
+    pub fn try_duration_actual(&self) -> Result<Duration, ClockError> {
+        Ok(self.try_stop()? - self.try_start()?)
+    }
+    /// The moment of the last recorded event (start, pause or adjustment)
+    fn last_event(&self) -> Option<DateTime<Local>> {
+        [
+            self.start_moments.last(),
+            self.pause_moments.last(),
+            self.adjustments.last().map(|adjustment| &adjustment.moment),
+        ]
+        .iter()
+        .flatten()
+        .max()
+        .map(|moment| **moment)
+    }
+}
+
+/// Retroactive editing, e.g. of a session in which a pause was forgotten. `remaining`,
+/// `overtime`, `expired_at` and the adjustments are recomputed from the edited moments. An edit
+/// that would leave the moments out of order is rejected and changes nothing.
+impl TimerData {
+    /// Insert a pause from `pause` to `resume`, within a single running segment
+    pub fn insert_pause(
+        &mut self,
+        pause: DateTime<Local>,
+        resume: DateTime<Local>,
+    ) -> Result<(), ClockError> {
+        self.edit(|data| {
+            segment::insert_pause(
+                &mut data.start_moments,
+                &mut data.pause_moments,
+                pause,
+                resume,
+            )
+        })
+    }
+    /// Move the `index`th start to `moment`
+    pub fn move_start(&mut self, index: usize, moment: DateTime<Local>) -> Result<(), ClockError> {
+        self.edit(|data| {
+            let start = data
+                .start_moments
+                .get_mut(index)
+                .ok_or(ClockError::NoSuchEvent { index })?;
+            *start = moment;
+            Ok(())
+        })
+    }
+    /// Move the `index`th pause to `moment`
+    pub fn move_pause(&mut self, index: usize, moment: DateTime<Local>) -> Result<(), ClockError> {
+        self.edit(|data| {
+            let pause = data
+                .pause_moments
+                .get_mut(index)
+                .ok_or(ClockError::NoSuchEvent { index })?;
+            *pause = moment;
+            Ok(())
+        })
+    }
+    /// Split the session at `moment`, keeping what happened until then and returning the rest,
+    /// which counts down from the time remaining at `moment`
+    pub fn split_off(&mut self, moment: DateTime<Local>) -> TimerData {
+        let total = self.original_total();
+        let mut rest = self.clip(Some(moment), None);
+        *self = self.clip(None, Some(moment));
+        self.replay(total);
+        rest.replay(self.remaining);
+        rest
+    }
+    /// Keep only what happened between `from` and `to`
+    pub fn trim(&mut self, from: DateTime<Local>, to: DateTime<Local>) -> Result<(), ClockError> {
+        if to < from {
+            return Err(ClockError::OutOfOrder {
+                moment: to,
+                last: from,
+            });
+        }
+        let total = self.original_total();
+        *self = self.clip(Some(from), Some(to));
+        self.replay(total);
+        Ok(())
+    }
+
+    /// Apply `edit` to a copy, and keep the copy if it is still consistent
+    fn edit(
+        &mut self,
+        edit: impl FnOnce(&mut Self) -> Result<(), ClockError>,
+    ) -> Result<(), ClockError> {
+        let mut data = self.clone();
+        edit(&mut data)?;
+        data.validate()?;
+        if let (Some(start), Some(adjustment)) =
+            (data.start_moments.first(), data.adjustments.first())
+        {
+            if adjustment.moment < *start {
+                return Err(ClockError::OutOfOrder {
+                    moment: adjustment.moment,
+                    last: *start,
+                });
+            }
+        }
+        data.replay(self.original_total());
+        *self = data;
+        Ok(())
+    }
+
+    /// What happened between `from` and `to` (either unbounded if `None`), not yet replayed
+    fn clip(&self, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> TimerData {
+        let (start_moments, pause_moments) =
+            segment::clip(&self.start_moments, &self.pause_moments, from, to);
+        TimerData {
+            start_moments,
+            pause_moments,
+            adjustments: self
+                .adjustments
+                .iter()
+                .copied()
+                .filter(|adjustment| {
+                    from.is_none_or(|from| from < adjustment.moment)
+                        && to.is_none_or(|to| adjustment.moment <= to)
+                })
+                .collect(),
+            ..TimerData::new(self.total)
+        }
     }
+
+    /// Recompute the countdown from `total`, replaying the starts, pauses and adjustments in
+    /// order. Adjustments keep their kind and amount.
+    fn replay(&mut self, total: Duration) {
+        enum Step {
+            Start,
+            Pause,
+            Adjust(usize),
+        }
+        let mut steps = Vec::new();
+        for (i, start) in self.start_moments.iter().enumerate() {
+            steps.push((*start, Step::Start));
+            steps.extend(self.pause_moments.get(i).map(|pause| (*pause, Step::Pause)));
+        }
+        steps.extend(
+            self.adjustments
+                .iter()
+                .enumerate()
+                .map(|(i, adjustment)| (adjustment.moment, Step::Adjust(i))),
+        );
+        // stable, so an adjustment comes after a start or pause at the same moment
+        steps.sort_by_key(|(moment, _)| *moment);
+
+        let (mut total, mut remaining) = (total, total); // remaining is negative in overtime
+        let mut expired_at = None;
+        let mut running_since = None;
+        for (moment, step) in steps {
+            if let Some(since) = running_since {
+                let before = remaining;
+                remaining -= moment - since;
+                if remaining <= Duration::zero() && expired_at.is_none() {
+                    expired_at = Some(since + before.max(Duration::zero()));
+                }
+            }
+            match step {
+                Step::Start => running_since = Some(moment),
+                Step::Pause => running_since = None,
+                Step::Adjust(i) => {
+                    let adjustment = &mut self.adjustments[i];
+                    let left = remaining.max(Duration::zero());
+                    let (new_total, new_remaining) = match adjustment.kind {
+                        AdjustmentKind::Add => {
+                            let amount = adjustment.total - adjustment.previous_total;
+                            (total + amount, left + amount)
+                        }
+                        AdjustmentKind::Subtract => {
+                            let amount = adjustment.previous_total - adjustment.total;
+                            let new_remaining = (left - amount).max(Duration::zero());
+                            (total - (left - new_remaining), new_remaining)
+                        }
+                        AdjustmentKind::Restart => (total, total),
+                        AdjustmentKind::SetTotal => {
+                            let run = total - left;
+                            (
+                                adjustment.total,
+                                (adjustment.total - run).max(Duration::zero()),
+                            )
+                        }
+                    };
+                    adjustment.previous_total = total;
+                    adjustment.total = new_total;
+                    adjustment.previous_remaining = remaining;
+                    adjustment.remaining = new_remaining;
+                    total = new_total;
+                    remaining = new_remaining;
+                    if remaining > Duration::zero() {
+                        expired_at = None;
+                    } else if expired_at.is_none() {
+                        expired_at = Some(moment);
+                    }
+                    if running_since.is_some() {
+                        running_since = Some(moment);
+                    }
+                }
+            }
+        }
+        self.total = total;
+        self.remaining = remaining.max(Duration::zero());
+        self.overtime = (-remaining).max(Duration::zero());
+        self.expired_at = expired_at;
+    }
+}
+
+/// When a [`Timer`](struct.Timer.html) should warn that time is running out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Warning {
+    /// When this much time remains, e.g. 5 minutes before the end of a talk
+    Remaining(#[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))] Duration),
+    /// When this percentage of the total remains
+    Percent(u32),
+    /// Each time this much more has run, e.g. every 10 minutes of a long timer
+    Every(#[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))] Duration),
+}
+
+impl Warning {
+    /// The remaining times at which this warning is due, in order, given that the remaining time
+    /// has gone from `checked.remaining` (exclusive) down to `remaining` (inclusive), and the run
+    /// time from `checked.run` (exclusive) up to `total - remaining` (inclusive)
+    fn thresholds(self, total: Duration, checked: Checked, remaining: Duration) -> Vec<Duration> {
+        let crossed =
+            |threshold: &Duration| checked.remaining > *threshold && *threshold >= remaining;
+        match self {
+            Warning::Remaining(remaining) => Some(remaining).filter(crossed).into_iter().collect(),
+            Warning::Percent(percent) => {
+                let threshold = total * percent.min(100) as i32 / 100;
+                Some(threshold).filter(crossed).into_iter().collect()
+            }
+            Warning::Every(interval) if interval > Duration::zero() => {
+                // the run time reaches `k * interval` at `total - k * interval` remaining, for
+                // each `k` from 1 while some time remains
+                let nanos = as_nanos(interval);
+                let first = as_nanos(checked.run).div_euclid(nanos).max(0) + 1;
+                let last = (as_nanos(total - remaining).div_euclid(nanos))
+                    .min((as_nanos(total) - 1).div_euclid(nanos));
+                (first..=last)
+                    .map(|k| total - Duration::nanoseconds((k * nanos) as i64))
+                    .collect()
+            }
+            Warning::Every(_) => Vec::new(),
+        }
+    }
+}
+
+/// How far the warnings of a timer have been checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Checked {
+    // the remaining time, for `Remaining` and `Percent` warnings; adding time raises it again
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    remaining: Duration,
+    // the time run (total - remaining), for `Every` warnings; only restarting lowers it again
+    #[cfg_attr(feature = "serde", serde(with = "crate::ser::duration"))]
+    run: Duration,
+}
+
+impl Checked {
+    fn new(total: Duration) -> Self {
+        Self {
+            remaining: total,
+            run: Duration::zero(),
+        }
+    }
+}
+
+impl Default for Checked {
+    fn default() -> Self {
+        Self::new(Duration::zero())
+    }
+}
+
+/// A warning threshold crossed by a [`Timer`](struct.Timer.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DueWarning {
+    pub warning: Warning,
+    pub remaining: Duration,     // the threshold crossed
+    pub moment: DateTime<Local>, // at which it was crossed
+}
+
+/// The state of a [`Timer`](struct.Timer.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerState {
+    /// Never started since initialised or stopped
+    Idle,
+    Running,
+    Paused,
+    /// The remaining time has reached zero, whether or not the timer is still running
+    Expired,
+}
+
+/// The state of a timer before an operation, for undoing it
+#[derive(Debug, Clone)]
+struct Snapshot {
+    paused: bool,
+    data: TimerData,
+    checked: Checked,
+    pending: Vec<DueWarning>,
+    anchor: Option<Anchor>,
 }
 
 /// A countdown timer
 #[derive(Clone, Debug)]
-pub struct Timer {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timer<C = SystemClock> {
     pub paused: bool,
+    pub overtime: bool, // keep counting (as negative durations) after expiry
     pub data: TimerData,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warnings: Vec<Warning>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    checked: Checked,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending: Vec<DueWarning>, // already notified, not yet returned by `.due_warnings()`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: C,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    anchor: Option<Anchor>, // set while running after a `.resume()` through the clock source
+    #[cfg_attr(feature = "serde", serde(skip))]
+    subscribers: Subscribers,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: History<Snapshot>,
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deadline: Deadline,
 }
 
 impl Timer {
     /// Returns stopwatch reset to zero
     pub fn new(duration: Duration) -> Self {
+        Self::with_clock(duration, SystemClock)
+    }
+}
+
+impl<C: ClockSource> Timer<C> {
+    /// `Timer::new(duration)` on another [clock source](../clock/index.html)
+    pub fn with_clock(duration: Duration, clock: C) -> Self {
         Self {
             paused: true, // finished by default; start by explicitly calling `.resume()`
+            overtime: false,
             data: TimerData::new(duration),
+            warnings: Vec::new(),
+            checked: Checked::new(duration),
+            pending: Vec::new(),
+            clock,
+            anchor: None,
+            subscribers: Subscribers::default(),
+            history: History::default(),
+            #[cfg(feature = "tokio")]
+            deadline: Deadline::default(),
+        }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// Revert the last pause, resume, stop or adjustment, restoring the data as it was before.
+    /// Returns whether there was one to revert.
+    pub fn undo(&mut self) -> bool {
+        if !self.history.can_undo() {
+            return false;
         }
+        let snapshot = self.history.undo(self.snapshot());
+        snapshot.map(|snapshot| self.restore(snapshot)).is_some()
+    }
+    /// Re-apply the last operation undone. Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        if !self.history.can_redo() {
+            return false;
+        }
+        let snapshot = self.history.redo(self.snapshot());
+        snapshot.map(|snapshot| self.restore(snapshot)).is_some()
+    }
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
     }
-    /// Read the timer. Returns the duration passed.
+    /// Keep at most `limit` operations to undo (32 by default)
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+    /// Call `callback` with each [`Event`](../event/enum.Event.html) from now on
+    pub fn subscribe(&mut self, callback: impl Fn(&Event) + Send + Sync + 'static) {
+        self.subscribers.subscribe(callback);
+    }
+    /// Send each [`Event`](../event/enum.Event.html) from now on through `sender`
+    pub fn subscribe_channel(&mut self, sender: Sender<Event>) {
+        self.subscribers.subscribe_channel(sender);
+    }
+    /// A stream of readings, the first at once and the following ones as the remaining time
+    /// reaches a multiple of `interval`. The stream borrows the timer; to operate it while
+    /// ticking, use `SharedTimer::ticks()`.
+    #[cfg(feature = "tokio")]
+    pub fn ticks(&self, interval: Duration) -> Ticks<'_>
+    where
+        C: Sync,
+    {
+        Ticks::new(interval, move || {
+            let moment = self.now();
+            let until = if self.paused {
+                None
+            } else {
+                Some(until_boundary(-self.raw_remaining_at(moment), interval))
+            };
+            (self.read_at(moment), until)
+        })
+    }
+    /// Resolves at the moment the timer expires (at once if it already has). Pausing, resuming
+    /// or extending the timer moves the expiry; stopping it or dropping it means it never comes.
+    #[cfg(feature = "tokio")]
+    pub fn expired(&self) -> impl std::future::Future<Output = DateTime<Local>> + Send + 'static {
+        self.deadline.expired()
+    }
+    /// Notify subscribers of the warnings that have become due, then record the expiry (and
+    /// notify them of it) if the timer has expired since the last update. Returns whether it
+    /// has. The warnings are still returned by the next `.due_warnings()`.
+    pub fn update(&mut self) -> bool {
+        self.update_at(self.now())
+    }
+
+    pub fn update_at(&mut self, moment: DateTime<Local>) -> bool {
+        self.check_warnings(moment);
+        if self.paused
+            || self.data.expired_at.is_some()
+            || self.raw_remaining_at(moment) > Duration::zero()
+        {
+            return false;
+        }
+        self.expire(self.last_settled() + self.data.remaining);
+        true
+    }
+    /// The warnings that have become due since the last call, in the order they became due,
+    /// each reported once per crossing of its threshold. Call this regularly (e.g. whenever you
+    /// redraw); subscribers are notified of each too.
+    pub fn due_warnings(&mut self) -> Vec<DueWarning> {
+        self.due_warnings_at(self.now())
+    }
+
+    pub fn due_warnings_at(&mut self, moment: DateTime<Local>) -> Vec<DueWarning> {
+        let remaining = self.raw_remaining_at(moment);
+        let mut due: Vec<DueWarning> = self
+            .warnings
+            .iter()
+            .flat_map(|warning| {
+                warning
+                    .thresholds(self.data.total, self.checked, remaining)
+                    .into_iter()
+                    .map(move |threshold| (*warning, threshold))
+            })
+            .map(|(warning, threshold)| DueWarning {
+                warning,
+                remaining: threshold,
+                moment: self.crossed_at(moment, threshold),
+            })
+            .collect();
+        due.sort_by_key(|warning| (warning.moment, -warning.remaining));
+        self.checked = Checked {
+            remaining: self.checked.remaining.min(remaining),
+            run: self.checked.run.max(self.data.total - remaining),
+        };
+        for warning in &due {
+            self.emit(Event::WarningReached {
+                moment: warning.moment,
+                remaining: warning.remaining,
+            });
+        }
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.append(&mut due);
+        pending
+    }
+    /// Add `duration` to the time remaining (and to the total). An expired timer gets `duration`
+    /// remaining, as if it had not expired. A negative `duration` is subtracted instead.
+    pub fn add_time(&mut self, duration: Duration) {
+        self.add_time_at(self.now(), duration);
+    }
+
+    pub fn add_time_at(&mut self, moment: DateTime<Local>, duration: Duration) {
+        if duration < Duration::zero() {
+            return self.subtract_time_at(moment, -duration);
+        }
+        let moment = self.clamp(moment);
+        let remaining = self.raw_remaining_at(moment).max(Duration::zero());
+        let total = self.data.total + duration;
+        self.adjust_at(moment, AdjustmentKind::Add, total, remaining + duration);
+    }
+    /// Take `duration` off the time remaining (and off the total). The timer expires if no time
+    /// is left. A negative `duration` is added instead.
+    pub fn subtract_time(&mut self, duration: Duration) {
+        self.subtract_time_at(self.now(), duration);
+    }
+
+    pub fn subtract_time_at(&mut self, moment: DateTime<Local>, duration: Duration) {
+        if duration < Duration::zero() {
+            return self.add_time_at(moment, -duration);
+        }
+        let moment = self.clamp(moment);
+        let before = self.raw_remaining_at(moment).max(Duration::zero());
+        let remaining = (before - duration).max(Duration::zero());
+        let total = self.data.total - (before - remaining);
+        self.adjust_at(moment, AdjustmentKind::Subtract, total, remaining);
+    }
+    /// Start the countdown over from the total, running or paused as it was
+    pub fn restart(&mut self) {
+        self.restart_at(self.now());
+    }
+
+    pub fn restart_at(&mut self, moment: DateTime<Local>) {
+        let moment = self.clamp(moment);
+        let total = self.data.total;
+        self.adjust_at(moment, AdjustmentKind::Restart, total, total);
+    }
+    /// Set the total to `total`, keeping the time already run. The timer expires if that is
+    /// already more than `total`. Before the timer is started, this just sets its duration. A
+    /// negative `total` counts as zero.
+    pub fn set_total(&mut self, total: Duration) {
+        self.set_total_at(self.now(), total);
+    }
+
+    pub fn set_total_at(&mut self, moment: DateTime<Local>, total: Duration) {
+        let moment = self.clamp(moment);
+        let run = self.data.total - self.raw_remaining_at(moment).max(Duration::zero());
+        let total = total.max(Duration::zero());
+        let remaining = (total - run).max(Duration::zero());
+        self.adjust_at(moment, AdjustmentKind::SetTotal, total, remaining);
+    }
+    /// Read the timer. Returns the duration remaining.
     pub fn read(&self) -> Duration {
-        if self.paused {
-            self.data.remaining
+        self.read_at(self.now())
+    }
+    /// Read the timer at `moment`. Returns the duration remaining.
+    pub fn read_at(&self, moment: DateTime<Local>) -> Duration {
+        let remaining = self.raw_remaining_at(moment);
+        if self.overtime {
+            remaining
         } else {
-            self.data.remaining - (Local::now() - self.last_start())
+            remaining.max(Duration::zero())
         }
     }
+    /// The time run past expiry
+    pub fn read_overtime(&self) -> Duration {
+        self.read_overtime_at(self.now())
+    }
+
+    pub fn read_overtime_at(&self, moment: DateTime<Local>) -> Duration {
+        (-self.raw_remaining_at(moment)).max(Duration::zero())
+    }
+    pub fn state(&self) -> TimerState {
+        self.state_at(self.now())
+    }
+
+    pub fn state_at(&self, moment: DateTime<Local>) -> TimerState {
+        if self.data.start_moments.is_empty() {
+            TimerState::Idle
+        } else if self.raw_remaining_at(moment) <= Duration::zero() {
+            TimerState::Expired
+        } else if self.paused {
+            TimerState::Paused
+        } else {
+            TimerState::Running
+        }
+    }
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(self.now())
+    }
+
+    pub fn is_expired_at(&self, moment: DateTime<Local>) -> bool {
+        self.state_at(moment) == TimerState::Expired
+    }
+    /// The moment at which the timer expires, or expired. `None` if it is idle, or paused
+    /// before expiry.
+    pub fn expires_at(&self) -> Option<DateTime<Local>> {
+        self.data.expired_at.or_else(|| {
+            if self.paused {
+                None
+            } else {
+                Some(self.last_settled() + self.data.remaining)
+            }
+        })
+    }
     /// Pause or resume the timer. (If paused, resume, and vice versa.)
     pub fn pause_or_resume(&mut self) {
-        self.pause_or_resume_at(Local::now());
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
     }
 
     pub fn pause_or_resume_at(&mut self, moment: DateTime<Local>) {
@@ -82,37 +769,686 @@ impl Timer {
 
     /// Pause the timer (suggest using `pause_or_resume` instead.)
     pub fn pause(&mut self) {
-        self.pause_at(Local::now());
+        self.pause_at(self.now());
     }
 
     pub fn pause_at(&mut self, moment: DateTime<Local>) {
-        self.data.pause_moments.push(moment);
-        self.data.remaining = self.data.remaining - (moment - self.last_start());
-        self.paused = true;
+        self.try_pause_at(self.clamp(moment)).ok();
     }
     /// Resume the timer (suggest using `pause_or_resume` instead.)
     pub fn resume(&mut self) {
-        self.resume_at(Local::now());
+        let moment = self.clamp(self.clock.now());
+        if self.try_resume_at(moment).is_ok() {
+            self.anchor = Some(Anchor::new(moment, &self.clock));
+        }
     }
 
     pub fn resume_at(&mut self, moment: DateTime<Local>) {
+        self.try_resume_at(self.clamp(moment)).ok();
+    }
+
+    /// Pause the timer, or return an error if it is already paused.
+    pub fn try_pause(&mut self) -> Result<(), ClockError> {
+        self.try_pause_at(self.now())
+    }
+
+    pub fn try_pause_at(&mut self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        if self.paused {
+            return Err(ClockError::NotRunning);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
+        self.record_pause(moment);
+        self.emit(Event::Paused { moment });
+        Ok(())
+    }
+    /// Resume the timer, or return an error if it is already running.
+    pub fn try_resume(&mut self) -> Result<(), ClockError> {
+        let moment = self.clock.now();
+        self.try_resume_at(moment)?;
+        self.anchor = Some(Anchor::new(moment, &self.clock));
+        Ok(())
+    }
+
+    pub fn try_resume_at(&mut self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        if !self.paused {
+            return Err(ClockError::AlreadyRunning);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
         self.data.start_moments.push(moment);
         self.paused = false;
+        self.anchor = None;
+        self.emit(if self.data.start_moments.len() == 1 {
+            Event::Started { moment }
+        } else {
+            Event::Resumed { moment }
+        });
+        Ok(())
+    }
+
+    /// The current moment. While running after a `.resume()`, this is measured on the monotonic
+    /// clock from the resume moment, so that wall-clock jumps do not affect elapsed time.
+    pub(crate) fn now(&self) -> DateTime<Local> {
+        match self.anchor {
+            Some(anchor) if !self.paused => anchor.now(&self.clock),
+            _ => self.clock.now(),
+        }
     }
 
-    /// Stop the timer, return the data, and reset the timer with the previously set duration.
+    /// Stop the timer, return the data, and reset the timer with the previously set duration
+    /// (before any adjustment).
     pub fn stop(&mut self) -> TimerData {
-        self.stop_at(Local::now())
+        self.stop_at(self.now())
     }
 
     pub fn stop_at(&mut self, moment: DateTime<Local>) -> TimerData {
-        self.data.pause_moments.push(moment);
-        let duration = self.data.total;
+        // a timer that was never started has nothing to return but its duration
+        self.try_stop_at(self.clamp(moment))
+            .unwrap_or_else(|_| TimerData::new(self.data.total))
+    }
+
+    /// Stop the timer, return the data, and reset the timer with the previously set duration,
+    /// or return an error if it was never started.
+    pub fn try_stop(&mut self) -> Result<TimerData, ClockError> {
+        self.try_stop_at(self.now())
+    }
+
+    pub fn try_stop_at(&mut self, moment: DateTime<Local>) -> Result<TimerData, ClockError> {
+        if self.data.start_moments.is_empty() {
+            return Err(ClockError::NotStarted);
+        }
+        self.check_order(moment)?;
+        self.history.record(self.snapshot());
+        if !self.paused {
+            self.record_pause(moment);
+        }
+        self.anchor = None;
+        let duration = self.data.original_total();
         let data = std::mem::replace(&mut self.data, TimerData::new(duration));
-        data
+        self.checked = Checked::new(duration);
+        self.pending.clear();
+        self.emit(Event::Stopped { moment });
+        Ok(data)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            paused: self.paused,
+            data: self.data.clone(),
+            checked: self.checked,
+            pending: self.pending.clone(),
+            anchor: self.anchor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.paused = snapshot.paused;
+        self.data = snapshot.data;
+        self.checked = snapshot.checked;
+        self.pending = snapshot.pending;
+        self.anchor = snapshot.anchor;
+        self.refresh_deadline();
+    }
+
+    fn record_pause(&mut self, moment: DateTime<Local>) {
+        self.check_warnings(moment);
+        self.settle(moment);
+        self.data.pause_moments.push(moment);
+        self.paused = true;
+    }
+
+    /// Notify subscribers of the warnings due by `moment`, before any expiry settled at it, and
+    /// keep them for `.due_warnings()`
+    fn check_warnings(&mut self, moment: DateTime<Local>) {
+        self.pending = self.due_warnings_at(moment);
+    }
+
+    /// Fold the time run since the last start or adjustment into the remaining time and
+    /// overtime
+    fn settle(&mut self, moment: DateTime<Local>) {
+        let since = self.last_settled();
+        let remaining = self.data.remaining - (moment - since);
+        if remaining <= Duration::zero() {
+            if self.data.expired_at.is_none() {
+                self.expire(since + self.data.remaining);
+            }
+            self.data.overtime -= remaining;
+            self.data.remaining = Duration::zero();
+        } else {
+            self.data.remaining = remaining;
+        }
+    }
+
+    /// Set the total and the time remaining at `moment`, recording the adjustment. Neither is
+    /// ever negative: time run past expiry is overtime.
+    fn adjust_at(
+        &mut self,
+        moment: DateTime<Local>,
+        kind: AdjustmentKind,
+        total: Duration,
+        remaining: Duration,
+    ) {
+        let total = total.max(Duration::zero());
+        let remaining = remaining.max(Duration::zero());
+        self.history.record(self.snapshot());
+        if self.data.start_moments.is_empty() {
+            // not started yet, so there is nothing to adjust but the duration
+            self.data.total = total;
+            self.data.remaining = total;
+            self.checked = Checked::new(total);
+            return;
+        }
+        // warnings due before the adjustment are due at the countdown as it was
+        self.check_warnings(moment);
+        if !self.paused {
+            self.settle(moment);
+        }
+        let previous_remaining = self.data.remaining - self.data.overtime;
+        self.data.adjustments.push(Adjustment {
+            kind,
+            moment,
+            previous_total: self.data.total,
+            total,
+            previous_remaining,
+            remaining,
+        });
+        self.data.total = total;
+        self.data.remaining = remaining;
+        // thresholds climbed back above are crossed again, and so are run times after a restart
+        self.checked = Checked {
+            remaining: self.checked.remaining.max(remaining),
+            run: self.checked.run.min(total - remaining),
+        };
+        if remaining > Duration::zero() {
+            self.data.expired_at = None;
+            self.data.overtime = Duration::zero();
+        } else if self.data.expired_at.is_none() {
+            self.expire(moment);
+        }
+        self.emit(Event::Adjusted { moment, remaining });
+    }
+
+    fn expire(&mut self, moment: DateTime<Local>) {
+        self.data.expired_at = Some(moment);
+        self.emit(Event::Expired { moment });
+    }
+
+    /// Notify subscribers and whoever awaits the expiry
+    fn emit(&self, event: Event) {
+        self.refresh_deadline();
+        self.subscribers.emit(event);
+    }
+
+    /// Let whoever awaits the expiry know that it may have moved
+    fn refresh_deadline(&self) {
+        #[cfg(feature = "tokio")]
+        self.deadline.set(self.now(), self.expires_at());
+    }
+
+    /// The moment at which the remaining time reached `threshold`, given that it had by `moment`
+    /// and not before the last adjustment. A threshold skipped by the adjustment itself was
+    /// reached at the adjustment.
+    fn crossed_at(&self, moment: DateTime<Local>, threshold: Duration) -> DateTime<Local> {
+        let (since, remaining) = match (
+            self.data.adjustments.last(),
+            self.data.start_moments.first(),
+        ) {
+            (Some(adjustment), _) => (adjustment.moment, adjustment.remaining),
+            (None, Some(start)) => (*start, self.data.total),
+            (None, None) => return moment,
+        };
+        if remaining <= threshold {
+            return since.min(moment);
+        }
+        segment::after_running(
+            &self.data.start_moments,
+            &self.data.pause_moments,
+            since,
+            remaining - threshold,
+        )
+        .map_or(moment, |crossed| crossed.min(moment))
     }
 
     fn last_start(&self) -> DateTime<Local> {
         self.data.start_moments[self.data.start_moments.len() - 1]
     }
+
+    /// The last start, or the last adjustment if made since; the remaining time and overtime
+    /// are as of this moment while running
+    fn last_settled(&self) -> DateTime<Local> {
+        match self.data.adjustments.last() {
+            Some(adjustment) => adjustment.moment.max(self.last_start()),
+            None => self.last_start(),
+        }
+    }
+
+    /// The remaining time at `moment`, negative once expired
+    fn raw_remaining_at(&self, moment: DateTime<Local>) -> Duration {
+        let remaining = self.data.remaining - self.data.overtime;
+        if self.paused {
+            remaining
+        } else {
+            remaining - (moment - self.last_settled())
+        }
+    }
+
+    fn check_order(&self, moment: DateTime<Local>) -> Result<(), ClockError> {
+        match self.data.last_event() {
+            Some(last) if moment < last => Err(ClockError::OutOfOrder { moment, last }),
+            _ => Ok(()),
+        }
+    }
+
+    /// `moment`, or the moment of the last recorded event if `moment` is earlier
+    fn clamp(&self, moment: DateTime<Local>) -> DateTime<Local> {
+        match self.data.last_event() {
+            Some(last) if moment < last => last,
+            _ => moment,
+        }
+    }
+}
+
+impl<C: ClockSource + Clone> Timer<C> {
+    /// Stop at `moment` and count down `duration` afresh on the same clock, as the next phase of
+    /// a [`Pomodoro`](../pomodoro/struct.Pomodoro.html) or interval of a
+    /// [`Sequence`](../sequence/struct.Sequence.html) does. If `start`, the new countdown starts
+    /// at `moment`, running on from the monotonic time of this one. Returns the data of this one.
+    pub(crate) fn begin_next_at(
+        &mut self,
+        moment: DateTime<Local>,
+        duration: Duration,
+        start: bool,
+    ) -> TimerData {
+        let anchor = match self.anchor {
+            Some(anchor) if !self.paused => Some(anchor.shifted(moment)),
+            _ => None,
+        };
+        let data = self.stop_at(moment);
+        *self = Timer::with_clock(duration, self.clock.clone());
+        if start {
+            match anchor {
+                Some(anchor) => {
+                    if self.try_resume_at(anchor.moment()).is_ok() {
+                        self.anchor = Some(anchor);
+                    }
+                }
+                None => self.resume_at(moment),
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: i64) -> Duration {
+        Duration::minutes(minutes)
+    }
+
+    fn seconds(seconds: i64) -> Duration {
+        Duration::seconds(seconds)
+    }
+
+    fn running(total: Duration) -> (Timer, DateTime<Local>) {
+        let start = Local::now();
+        let mut timer = Timer::new(total);
+        timer.resume_at(start);
+        (timer, start)
+    }
+
+    #[test]
+    fn read_with_manual_clock() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(Local::now());
+        let mut timer = Timer::with_clock(minutes(1), clock.clone());
+        timer.resume();
+        clock.advance(seconds(20));
+        clock.set(clock.now() + minutes(30)); // the wall clock jumps forward
+        assert_eq!(timer.read(), seconds(40));
+        assert!(!timer.is_expired());
+        clock.advance(seconds(40));
+        assert_eq!(timer.read(), Duration::zero());
+        assert!(timer.is_expired());
+    }
+
+    #[test]
+    fn undo_stop_and_adjustments() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(Local::now());
+        let mut timer = Timer::with_clock(minutes(10), clock.clone());
+        timer.resume();
+        clock.advance(minutes(2));
+        timer.add_time(minutes(5));
+        clock.advance(minutes(1));
+        timer.stop();
+        assert!(timer.undo());
+        assert!(!timer.paused);
+        clock.set(clock.now() + minutes(30));
+        clock.advance(minutes(1));
+        assert_eq!(timer.read(), minutes(11));
+
+        assert!(timer.undo());
+        assert_eq!(timer.data.total, minutes(10));
+        assert!(timer.data.adjustments.is_empty());
+        assert_eq!(timer.read(), minutes(6));
+        assert!(timer.redo());
+        assert_eq!(timer.read(), minutes(11));
+        timer.pause();
+        assert!(!timer.redo());
+    }
+
+    #[test]
+    fn add_time() {
+        let (mut timer, start) = running(minutes(10));
+        timer.add_time_at(start + minutes(4), minutes(2));
+        assert_eq!(timer.read_at(start + minutes(4)), minutes(8));
+        assert_eq!(timer.data.total, minutes(12));
+        assert_eq!(
+            timer.data.adjustments,
+            vec![Adjustment {
+                kind: AdjustmentKind::Add,
+                moment: start + minutes(4),
+                previous_total: minutes(10),
+                total: minutes(12),
+                previous_remaining: minutes(6),
+                remaining: minutes(8),
+            }]
+        );
+        assert_eq!(timer.expires_at(), Some(start + minutes(12)));
+    }
+
+    #[test]
+    fn add_time_after_expiry() {
+        let (mut timer, start) = running(minutes(5));
+        timer.add_time_at(start + minutes(7), minutes(1));
+        assert_eq!(timer.data.adjustments[0].previous_remaining, minutes(-2));
+        assert_eq!(timer.data.expired_at, None);
+        assert_eq!(timer.data.overtime, Duration::zero());
+        assert_eq!(timer.state_at(start + minutes(7)), TimerState::Running);
+        assert_eq!(timer.expires_at(), Some(start + minutes(8)));
+    }
+
+    #[test]
+    fn subtract_time() {
+        let (mut timer, start) = running(minutes(10));
+        timer.subtract_time_at(start + minutes(3), minutes(2));
+        assert_eq!(timer.read_at(start + minutes(4)), minutes(4));
+        assert_eq!(timer.data.total, minutes(8));
+        // more than remains
+        timer.subtract_time_at(start + minutes(5), minutes(20));
+        assert_eq!(timer.data.total, minutes(5));
+        assert_eq!(timer.data.expired_at, Some(start + minutes(5)));
+        assert!(timer.is_expired_at(start + minutes(5)));
+    }
+
+    #[test]
+    fn negative_adjustments() {
+        let (mut timer, start) = running(minutes(10));
+        timer.add_time_at(start + minutes(2), minutes(-20));
+        assert_eq!(timer.data.total, minutes(2));
+        assert_eq!(timer.data.remaining, Duration::zero());
+        assert_eq!(timer.data.adjustments[0].kind, AdjustmentKind::Subtract);
+        let data = timer.stop_at(start + minutes(4));
+        assert_eq!(data.expired_at, Some(start + minutes(2)));
+        assert_eq!(data.overtime, minutes(2));
+
+        let (mut timer, start) = running(minutes(10));
+        timer.subtract_time_at(start + minutes(2), minutes(-5));
+        assert_eq!(timer.data.total, minutes(15));
+        assert_eq!(timer.read_at(start + minutes(2)), minutes(13));
+    }
+
+    #[test]
+    fn negative_total() {
+        let mut timer = Timer::new(minutes(10));
+        timer.set_total(minutes(-5));
+        assert_eq!(timer.data.total, Duration::zero());
+        assert_eq!(timer.data.remaining, Duration::zero());
+
+        let (mut timer, start) = running(minutes(10));
+        timer.set_total_at(start + minutes(2), minutes(-5));
+        assert_eq!(timer.data.total, Duration::zero());
+        assert_eq!(timer.data.remaining, Duration::zero());
+        assert_eq!(timer.data.expired_at, Some(start + minutes(2)));
+        assert_eq!(timer.stop_at(start + minutes(3)).overtime, minutes(1));
+    }
+
+    #[test]
+    fn restart_and_set_total() {
+        let (mut timer, start) = running(minutes(10));
+        timer.pause_at(start + minutes(4));
+        timer.restart_at(start + minutes(5));
+        assert!(timer.paused);
+        assert_eq!(timer.read(), minutes(10));
+
+        timer.resume_at(start + minutes(6));
+        timer.set_total_at(start + minutes(8), minutes(30));
+        assert_eq!(timer.read_at(start + minutes(8)), minutes(28));
+        timer.set_total_at(start + minutes(9), minutes(2));
+        assert_eq!(timer.data.expired_at, Some(start + minutes(9)));
+        assert_eq!(timer.data.adjustments.len(), 3);
+    }
+
+    #[test]
+    fn stop_resets_to_the_original_total() {
+        let (mut timer, start) = running(minutes(10));
+        timer.add_time_at(start + minutes(1), minutes(5));
+        let data = timer.stop_at(start + minutes(2));
+        assert_eq!(data.total, minutes(15));
+        assert_eq!(data.remaining, minutes(13));
+        assert_eq!(data.original_total(), minutes(10));
+        assert_eq!(timer.data.total, minutes(10));
+        assert_eq!(timer.state(), TimerState::Idle);
+    }
+
+    #[test]
+    fn adjust_before_starting() {
+        let mut timer = Timer::new(minutes(10));
+        timer.set_total(minutes(20));
+        timer.add_time(minutes(1));
+        assert_eq!(timer.read(), minutes(21));
+        assert!(timer.data.adjustments.is_empty());
+    }
+
+    /// 10 minutes, run for 6 with a pause of a minute, and a minute added after 2
+    fn session() -> (TimerData, DateTime<Local>) {
+        let (mut timer, start) = running(minutes(10));
+        timer.add_time_at(start + minutes(2), minutes(1));
+        timer.pause_at(start + minutes(4));
+        timer.resume_at(start + minutes(5));
+        (timer.stop_at(start + minutes(7)), start)
+    }
+
+    #[test]
+    fn insert_pause_replays_adjustments() {
+        let (mut data, start) = session();
+        assert_eq!(data.remaining, minutes(5));
+        data.insert_pause(start + minutes(1), start + seconds(90))
+            .unwrap();
+        assert_eq!(data.remaining, seconds(330));
+        assert_eq!(data.adjustments[0].previous_remaining, seconds(510));
+        assert_eq!(data.adjustments[0].remaining, seconds(570));
+        assert_eq!(data.total, minutes(11));
+    }
+
+    #[test]
+    fn move_start_and_pause() {
+        let (mut data, start) = session();
+        data.move_pause(1, start + minutes(13)).unwrap();
+        assert_eq!(data.remaining, Duration::zero());
+        assert_eq!(data.overtime, minutes(1));
+        assert_eq!(data.expired_at, Some(start + minutes(12)));
+
+        let before = data.clone();
+        assert_eq!(
+            data.move_start(1, start + minutes(3)),
+            Err(ClockError::NotInterleaved { index: 2 })
+        );
+        // after the adjustment made while running
+        assert_eq!(
+            data.move_start(0, start + minutes(3)),
+            Err(ClockError::OutOfOrder {
+                moment: start + minutes(2),
+                last: start + minutes(3)
+            })
+        );
+        assert_eq!(
+            data.move_pause(2, start),
+            Err(ClockError::NoSuchEvent { index: 2 })
+        );
+        assert_eq!(data.start_moments, before.start_moments);
+        assert_eq!(data.pause_moments, before.pause_moments);
+        assert_eq!(data.overtime, before.overtime);
+    }
+
+    #[test]
+    fn split_off_and_trim() {
+        let (mut data, start) = session();
+        let rest = data.split_off(start + minutes(3));
+        assert_eq!(data.remaining, minutes(8));
+        assert_eq!(data.adjustments.len(), 1);
+        assert_eq!(rest.total, minutes(8));
+        assert_eq!(rest.remaining, minutes(5));
+        assert!(rest.adjustments.is_empty());
+
+        let (mut data, start) = session();
+        data.trim(start + minutes(3), start + minutes(6)).unwrap();
+        assert_eq!(data.active_time(), minutes(2));
+        assert_eq!(data.remaining, minutes(8));
+        assert!(data.adjustments.is_empty());
+    }
+
+    #[test]
+    fn warnings_before_expiry() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (mut timer, start) = running(minutes(10));
+        timer.warnings.push(Warning::Remaining(minutes(5)));
+        timer.subscribe_channel(sender);
+        assert!(timer.update_at(start + minutes(11)));
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                Event::WarningReached {
+                    moment: start + minutes(5),
+                    remaining: minutes(5),
+                },
+                Event::Expired {
+                    moment: start + minutes(10),
+                },
+            ]
+        );
+        // still returned once, but not notified again
+        assert_eq!(timer.due_warnings_at(start + minutes(11)).len(), 1);
+        assert_eq!(timer.due_warnings_at(start + minutes(12)), vec![]);
+        assert_eq!(receiver.try_iter().count(), 0);
+
+        // and when pausing past the expiry
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (mut timer, start) = running(minutes(10));
+        timer.warnings.push(Warning::Percent(20));
+        timer.subscribe_channel(sender);
+        timer.pause_at(start + minutes(11));
+        let events: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(
+            events[..2],
+            [
+                Event::WarningReached {
+                    moment: start + minutes(8),
+                    remaining: minutes(2),
+                },
+                Event::Expired {
+                    moment: start + minutes(10),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn every_warning_once_after_subtracting() {
+        let (mut timer, start) = running(minutes(30));
+        timer.warnings.push(Warning::Every(minutes(10)));
+        let due = timer.due_warnings_at(start + minutes(12));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].remaining, minutes(20));
+        assert_eq!(due[0].moment, start + minutes(10));
+
+        timer.subtract_time_at(start + minutes(12), minutes(5));
+        assert_eq!(timer.due_warnings_at(start + minutes(13)), vec![]);
+        let due = timer.due_warnings_at(start + minutes(21));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].remaining, minutes(5));
+        assert_eq!(due[0].moment, start + minutes(20));
+    }
+
+    #[test]
+    fn warnings_crossed_before_a_pause() {
+        let (mut timer, start) = running(minutes(60));
+        timer.warnings.push(Warning::Every(minutes(10)));
+        timer.pause_at(start + minutes(35));
+        let moments: Vec<_> = timer
+            .due_warnings_at(start + minutes(40))
+            .iter()
+            .map(|warning| warning.moment)
+            .collect();
+        assert_eq!(
+            moments,
+            vec![
+                start + minutes(10),
+                start + minutes(20),
+                start + minutes(30)
+            ]
+        );
+        assert_eq!(timer.due_warnings_at(start + minutes(50)), vec![]);
+    }
+
+    #[test]
+    fn remaining_warning_again_after_adding_time() {
+        let (mut timer, start) = running(minutes(10));
+        timer.warnings.push(Warning::Remaining(minutes(5)));
+        // not checked before the adjustment, but still reported as crossed then
+        timer.add_time_at(start + minutes(6), minutes(3));
+        let due = timer.due_warnings_at(start + minutes(7));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].moment, start + minutes(5));
+
+        let due = timer.due_warnings_at(start + minutes(9));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].moment, start + minutes(8));
+        assert_eq!(timer.due_warnings_at(start + minutes(20)), vec![]);
+    }
+
+    #[test]
+    fn warnings_skipped_by_subtracting() {
+        let (mut timer, start) = running(minutes(20));
+        timer.warnings.push(Warning::Remaining(minutes(5)));
+        timer.warnings.push(Warning::Percent(40)); // of the new total, so 4 minutes
+        timer.subtract_time_at(start + minutes(6), minutes(10));
+        let due = timer.due_warnings_at(start + minutes(7));
+        assert_eq!(
+            due.iter()
+                .map(|warning| warning.warning)
+                .collect::<Vec<_>>(),
+            vec![Warning::Remaining(minutes(5)), Warning::Percent(40)]
+        );
+        assert!(due
+            .iter()
+            .all(|warning| warning.moment == start + minutes(6)));
+    }
+
+    #[test]
+    fn every_warning_again_after_restarting() {
+        let (mut timer, start) = running(minutes(10));
+        timer.warnings.push(Warning::Every(minutes(4)));
+        assert_eq!(timer.due_warnings_at(start + minutes(5)).len(), 1);
+        timer.restart_at(start + minutes(5));
+        let due = timer.due_warnings_at(start + minutes(13));
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].moment, start + minutes(9));
+        assert_eq!(due[1].moment, start + minutes(13));
+    }
 }
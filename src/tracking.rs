@@ -0,0 +1,311 @@
+//! # Tracking
+//!
+//! Time-tracking sessions: the [`StopwatchData`](../stopwatch/struct.StopwatchData.html) of a
+//! stopped stopwatch, with the project it was spent on, tags and notes, kept in a
+//! [`Journal`](struct.Journal.html).
+//!
+//! ## Usage
+//!
+//! - Use `Journal::new()` to initialise an empty journal, and `.record(<project>, <data>)` to
+//!   add the data returned by `Stopwatch::stop()` as a [`Session`](struct.Session.html). It
+//!   returns the [`SessionId`](struct.SessionId.html) by which you can get the session back with
+//!   `.session_mut(<id>)`, e.g. to add tags or notes.
+//! - Build a [`Filter`](struct.Filter.html) to select sessions by project, tags and date range,
+//!   and call `.sessions(<filter>)` for them, `.total(<filter>)` for the time spent on them, or
+//!   `.totals_by_project(<filter>)` for the time spent on each project. With a date range, only
+//!   the time within it counts.
+
+use crate::segment;
+use crate::stopwatch::StopwatchData;
+use chrono::{DateTime, Duration, Local};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Identifies a session in a [`Journal`](struct.Journal.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionId(pub u64);
+
+/// Time spent on a project
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Session {
+    pub id: SessionId,
+    pub project: String,
+    pub tags: BTreeSet<String>,
+    pub notes: String,
+    pub data: StopwatchData,
+}
+
+impl Session {
+    pub fn new(id: SessionId, project: impl Into<String>, data: StopwatchData) -> Self {
+        Self {
+            id,
+            project: project.into(),
+            tags: BTreeSet::new(),
+            notes: String::new(),
+            data,
+        }
+    }
+    /// Add `tag`. Returns whether it was not already there.
+    pub fn tag(&mut self, tag: impl Into<String>) -> bool {
+        self.tags.insert(tag.into())
+    }
+    /// Remove `tag`. Returns whether it was there.
+    pub fn untag(&mut self, tag: &str) -> bool {
+        self.tags.remove(tag)
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+    /// The moment at which the session started, or `None` if it never did
+    pub fn start(&self) -> Option<DateTime<Local>> {
+        self.data.start_moments.first().copied()
+    }
+    /// The moment at which the session last paused (i.e. ended), or `None` if it never did
+    pub fn end(&self) -> Option<DateTime<Local>> {
+        self.data.pause_moments.last().copied()
+    }
+    /// The time spent, excluding pauses
+    pub fn duration(&self) -> Duration {
+        self.data.active_time()
+    }
+    /// The time spent between `from` and `to` (either unbounded if `None`), excluding pauses
+    pub fn duration_between(
+        &self,
+        from: Option<DateTime<Local>>,
+        to: Option<DateTime<Local>>,
+    ) -> Duration {
+        // a segment still running has no end, so it does not count
+        let (starts, pauses) = (&self.data.start_moments, &self.data.pause_moments);
+        let complete = &starts[..pauses.len().min(starts.len())];
+        let (starts, pauses) = segment::clip(complete, pauses, from, to);
+        segment::total(segment::running(&starts, &pauses))
+    }
+}
+
+/// Which sessions to select from a [`Journal`](struct.Journal.html). The default selects all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Filter {
+    pub project: Option<String>,
+    pub tags: BTreeSet<String>,        // a session must have all of them
+    pub from: Option<DateTime<Local>>, // a session must end after this
+    pub to: Option<DateTime<Local>>,   // a session must start before this
+}
+
+impl Filter {
+    /// Select all sessions
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Select only sessions on `project`
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+    /// Select only sessions tagged `tag` (and any other tags required)
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+    /// Select only sessions between `from` and `to`
+    pub fn between(mut self, from: DateTime<Local>, to: DateTime<Local>) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+    pub fn matches(&self, session: &Session) -> bool {
+        let in_range = match session.start() {
+            Some(start) => {
+                let end = session.end().map_or(start, |end| end.max(start));
+                self.from.is_none_or(|from| end > from) && self.to.is_none_or(|to| start < to)
+            }
+            // a session never started has no date
+            None => self.from.is_none() && self.to.is_none(),
+        };
+        in_range && self.matches_labels(session)
+    }
+
+    fn matches_labels(&self, session: &Session) -> bool {
+        self.project
+            .as_ref()
+            .is_none_or(|project| *project == session.project)
+            && self.tags.iter().all(|tag| session.has_tag(tag))
+    }
+}
+
+/// Time-tracking sessions, in the order they were recorded
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Journal {
+    pub sessions: BTreeMap<SessionId, Session>,
+    next_id: u64,
+}
+
+impl Journal {
+    /// Returns an empty journal
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add the data of a stopped stopwatch as a session on `project`
+    pub fn record(&mut self, project: impl Into<String>, data: StopwatchData) -> SessionId {
+        self.next_id += 1;
+        let id = SessionId(self.next_id);
+        self.sessions.insert(id, Session::new(id, project, data));
+        id
+    }
+    pub fn session(&self, id: SessionId) -> Option<&Session> {
+        self.sessions.get(&id)
+    }
+    pub fn session_mut(&mut self, id: SessionId) -> Option<&mut Session> {
+        self.sessions.get_mut(&id)
+    }
+    pub fn remove(&mut self, id: SessionId) -> Option<Session> {
+        self.sessions.remove(&id)
+    }
+    /// The sessions selected by `filter`, in the order they were recorded
+    pub fn sessions<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Session> + 'a {
+        self.sessions
+            .values()
+            .filter(move |session| filter.matches(session))
+    }
+    /// The projects of all sessions
+    pub fn projects(&self) -> BTreeSet<&str> {
+        self.sessions
+            .values()
+            .map(|session| session.project.as_str())
+            .collect()
+    }
+    /// The time spent on the sessions selected by `filter`, within its date range
+    pub fn total(&self, filter: &Filter) -> Duration {
+        self.sessions(filter)
+            .fold(Duration::zero(), |total, session| {
+                total + session.duration_between(filter.from, filter.to)
+            })
+    }
+    /// The time spent on each project in the sessions selected by `filter`, within its date
+    /// range
+    pub fn totals_by_project(&self, filter: &Filter) -> BTreeMap<&str, Duration> {
+        let mut totals = BTreeMap::new();
+        let sessions = self
+            .sessions
+            .values()
+            .filter(|session| filter.matches(session));
+        for session in sessions {
+            *totals
+                .entry(session.project.as_str())
+                .or_insert_with(Duration::zero) += session.duration_between(filter.from, filter.to);
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stopwatch::Stopwatch;
+
+    fn minutes(minutes: i64) -> Duration {
+        Duration::minutes(minutes)
+    }
+
+    /// Running from `from` to `to` minutes after `start`, with the pauses between
+    fn data(start: DateTime<Local>, segments: &[(i64, i64)]) -> StopwatchData {
+        let mut stopwatch = Stopwatch::new();
+        for (from, to) in segments {
+            stopwatch.resume_at(start + minutes(*from));
+            stopwatch.pause_at(start + minutes(*to));
+        }
+        let end = segments.last().map_or(0, |(_, to)| *to);
+        stopwatch.stop_at(start + minutes(end))
+    }
+
+    /// Writing for two hours with an hour's break, then reading for an hour
+    fn journal() -> (Journal, [SessionId; 2], DateTime<Local>) {
+        let start = Local::now();
+        let mut journal = Journal::new();
+        let writing = journal.record("book", data(start, &[(0, 60), (120, 180)]));
+        let reading = journal.record("research", data(start, &[(180, 240)]));
+        journal.session_mut(writing).unwrap().tag("draft");
+        (journal, [writing, reading], start)
+    }
+
+    #[test]
+    fn duration_between() {
+        let (journal, [writing, _], start) = journal();
+        let session = journal.session(writing).unwrap();
+        assert_eq!(session.duration(), minutes(120));
+        assert_eq!(session.duration_between(None, None), minutes(120));
+        // clipped to the range, excluding the pause
+        let (from, to) = (Some(start + minutes(30)), Some(start + minutes(150)));
+        assert_eq!(session.duration_between(from, to), minutes(60));
+        assert_eq!(session.duration_between(from, None), minutes(90));
+        assert_eq!(
+            session.duration_between(Some(start + minutes(60)), Some(start + minutes(120))),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn running_segment_does_not_count() {
+        let start = Local::now();
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.resume_at(start);
+        stopwatch.pause_at(start + minutes(10));
+        stopwatch.resume_at(start + minutes(20));
+        let session = Session::new(SessionId(1), "book", stopwatch.data.clone());
+        assert_eq!(session.duration_between(None, None), minutes(10));
+    }
+
+    #[test]
+    fn filter_by_date_range() {
+        let (journal, [writing, reading], start) = journal();
+        let ids = |filter: &Filter| -> Vec<SessionId> {
+            journal.sessions(filter).map(|session| session.id).collect()
+        };
+        let between = |from, to| Filter::new().between(start + minutes(from), start + minutes(to));
+        assert_eq!(ids(&Filter::new()), vec![writing, reading]);
+        assert_eq!(ids(&between(30, 90)), vec![writing]);
+        // a session must end after `from` and start before `to`
+        assert_eq!(ids(&between(180, 200)), vec![reading]);
+        assert_eq!(ids(&between(-60, 0)), Vec::<SessionId>::new());
+        assert_eq!(ids(&between(240, 300)), Vec::<SessionId>::new());
+
+        assert_eq!(journal.total(&between(150, 210)), minutes(60));
+        assert_eq!(journal.total(&Filter::new()), minutes(180));
+    }
+
+    #[test]
+    fn never_started_sessions_have_no_date() {
+        let start = Local::now();
+        let session = Session::new(SessionId(1), "book", StopwatchData::default());
+        assert!(Filter::new().matches(&session));
+        assert!(!Filter::new()
+            .between(start - minutes(60), start + minutes(60))
+            .matches(&session));
+    }
+
+    #[test]
+    fn filter_by_labels() {
+        let (journal, [writing, reading], start) = journal();
+        let ids = |filter: &Filter| -> Vec<SessionId> {
+            journal.sessions(filter).map(|session| session.id).collect()
+        };
+        assert_eq!(ids(&Filter::new().project("research")), vec![reading]);
+        assert_eq!(ids(&Filter::new().tag("draft")), vec![writing]);
+        assert_eq!(
+            ids(&Filter::new().tag("draft").tag("final")),
+            Vec::<SessionId>::new()
+        );
+
+        let totals = journal.totals_by_project(&Filter::new().between(start, start + minutes(210)));
+        assert_eq!(
+            totals.into_iter().collect::<Vec<_>>(),
+            vec![("book", minutes(120)), ("research", minutes(30))]
+        );
+        assert_eq!(
+            journal.projects().into_iter().collect::<Vec<_>>(),
+            vec!["book", "research"]
+        );
+    }
+}
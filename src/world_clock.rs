@@ -0,0 +1,106 @@
+//! # World clock
+//!
+//! A world clock that mimics iOS's world clock. Requires the `chrono-tz` feature.
+//!
+//! ## Usage
+//!
+//! - Use `WorldClock::new()` to initialise an empty world clock, and `.add(<name>, <tz>)` to
+//!   add zones to it, where `<tz>` is a `chrono_tz::Tz` (e.g. `chrono_tz::Asia::Tokyo`, or
+//!   `"Asia/Tokyo".parse()`).
+//! - Call `.read()` to get a [`ZoneReading`](struct.ZoneReading.html) for each zone, in the
+//!   order they were added.
+
+use crate::clock::{ClockSource, SystemClock};
+use chrono::{DateTime, Duration, FixedOffset, Local, Offset};
+use chrono_tz::{OffsetComponents, Tz};
+
+/// A named time zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    pub name: String,
+    pub tz: Tz,
+}
+
+/// The time in a [`Zone`](struct.Zone.html) at a given moment
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneReading {
+    pub name: String,
+    pub time: DateTime<Tz>,
+    pub utc_offset: FixedOffset,
+    pub day_difference: i64, // calendar days ahead of (or, if negative, behind) the local date
+    pub dst: bool,           // whether daylight saving time is in effect
+}
+
+impl ZoneReading {
+    /// The offset from the local time zone, e.g. `+8 hours` for Tokyo seen from London in winter
+    pub fn offset_from_local(&self) -> Duration {
+        let local = self.time.with_timezone(&Local).offset().fix();
+        Duration::seconds(i64::from(
+            self.utc_offset.local_minus_utc() - local.local_minus_utc(),
+        ))
+    }
+}
+
+/// A list of named time zones
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldClock<C = SystemClock> {
+    pub zones: Vec<Zone>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: C,
+}
+
+impl WorldClock {
+    /// Returns a world clock with no zones
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C: ClockSource> WorldClock<C> {
+    /// `WorldClock::new()` on another [clock source](../clock/index.html)
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            zones: Vec::new(),
+            clock,
+        }
+    }
+    /// The clock source
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+    /// Add a zone called `name`
+    pub fn add(&mut self, name: impl Into<String>, tz: Tz) {
+        self.zones.push(Zone {
+            name: name.into(),
+            tz,
+        });
+    }
+    /// Remove all zones called `name`. Returns whether any was removed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.zones.len();
+        self.zones.retain(|zone| zone.name != name);
+        self.zones.len() != len
+    }
+    /// Read the time in each zone
+    pub fn read(&self) -> Vec<ZoneReading> {
+        self.read_at(self.clock.now())
+    }
+
+    pub fn read_at(&self, moment: DateTime<Local>) -> Vec<ZoneReading> {
+        self.zones
+            .iter()
+            .map(|zone| {
+                let time = moment.with_timezone(&zone.tz);
+                ZoneReading {
+                    name: zone.name.clone(),
+                    utc_offset: time.offset().fix(),
+                    day_difference: (time.date_naive() - moment.date_naive()).num_days(),
+                    dst: !time.offset().dst_offset().is_zero(),
+                    time,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+//! Calendar totals across the daylight saving transitions of Europe/London. They run in their
+//! own test binary, since the local time zone can only be pinned for the whole process.
+
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use clock_core::calendar::{Calendar, Period};
+use clock_core::segment::Span;
+use std::sync::Once;
+
+/// A moment in UTC, seen in Europe/London
+fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    static LONDON: Once = Once::new();
+    LONDON.call_once(|| std::env::set_var("TZ", "Europe/London"));
+    Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+fn totals(calendar: Calendar, period: Period, segment: Span) -> Vec<(DateTime<Local>, Duration)> {
+    calendar.totals(period, Some(segment)).into_iter().collect()
+}
+
+#[test]
+fn clocks_going_forward() {
+    // 1 am GMT became 2 am BST on 31 March 2024, so the day was 23 hours long
+    let day = Calendar::default().bounds(Period::Day, utc(3, 31, 12, 0));
+    assert_eq!(day, (utc(3, 31, 0, 0), utc(3, 31, 23, 0)));
+
+    // 11 pm to 3 am local time, of which 3 hours elapsed
+    let segment = Span::new(utc(3, 30, 23, 0), utc(3, 31, 2, 0));
+    assert_eq!(
+        totals(Calendar::default(), Period::Day, segment),
+        vec![
+            (utc(3, 30, 0, 0), Duration::hours(1)),
+            (utc(3, 31, 0, 0), Duration::hours(2)),
+        ]
+    );
+    assert_eq!(
+        totals(Calendar::default(), Period::Hour, segment),
+        vec![
+            (utc(3, 30, 23, 0), Duration::hours(1)),
+            (utc(3, 31, 0, 0), Duration::hours(1)),
+            (utc(3, 31, 1, 0), Duration::hours(1)),
+        ]
+    );
+
+    // a day starting at 1 am starts just after the skipped hour
+    let calendar = Calendar {
+        rollover_hour: 1,
+        ..Calendar::default()
+    };
+    assert_eq!(
+        calendar.bounds(Period::Day, utc(3, 31, 12, 0)),
+        (utc(3, 31, 1, 0), utc(4, 1, 0, 0))
+    );
+}
+
+#[test]
+fn clocks_going_back() {
+    // 2 am BST became 1 am GMT on 27 October 2024, so the day was 25 hours long
+    let day = Calendar::default().bounds(Period::Day, utc(10, 27, 12, 0));
+    assert_eq!(day, (utc(10, 26, 23, 0), utc(10, 28, 0, 0)));
+
+    // midnight to 3 am local time, of which 4 hours elapsed
+    let segment = Span::new(utc(10, 26, 23, 0), utc(10, 27, 3, 0));
+    assert_eq!(
+        totals(Calendar::default(), Period::Day, segment),
+        vec![(utc(10, 26, 23, 0), Duration::hours(4))]
+    );
+    // the hour from 1 am counts twice
+    assert_eq!(
+        totals(Calendar::default(), Period::Hour, segment),
+        vec![
+            (utc(10, 26, 23, 0), Duration::hours(1)),
+            (utc(10, 27, 0, 0), Duration::hours(1)),
+            (utc(10, 27, 1, 0), Duration::hours(1)),
+            (utc(10, 27, 2, 0), Duration::hours(1)),
+        ]
+    );
+}
+
+#[test]
+fn weeks_across_a_transition() {
+    // the week of Monday 25 March 2024 starts in GMT and ends in BST
+    let segment = Span::new(utc(3, 31, 22, 0), utc(4, 1, 0, 0));
+    assert_eq!(
+        totals(Calendar::default(), Period::Week, segment),
+        vec![
+            (utc(3, 25, 0, 0), Duration::hours(1)),
+            (utc(3, 31, 23, 0), Duration::hours(1)),
+        ]
+    );
+}
@@ -0,0 +1,71 @@
+//! Countdown breakdowns across the daylight saving transitions of Europe/London. They run in
+//! their own test binary, since the local time zone can only be pinned for the whole process.
+
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use clock_core::countdown::{Breakdown, Countdown};
+use std::sync::Once;
+
+/// A moment in UTC, seen in Europe/London
+fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    static LONDON: Once = Once::new();
+    LONDON.call_once(|| std::env::set_var("TZ", "Europe/London"));
+    Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+fn breakdown(from: DateTime<Local>, to: DateTime<Local>) -> Breakdown {
+    Countdown::new(to).breakdown_at(from)
+}
+
+fn days_hours_minutes(days: i64, hours: i64, minutes: i64) -> Breakdown {
+    Breakdown {
+        days,
+        hours,
+        minutes,
+        ..Breakdown::default()
+    }
+}
+
+#[test]
+fn clocks_going_forward() {
+    // 1 am GMT became 2 am BST on 31 March 2024
+    // from noon on Saturday to noon on Monday, 47 hours apart
+    let (from, to) = (utc(3, 30, 12, 0), utc(4, 1, 11, 0));
+    assert_eq!(Countdown::new(to).read_at(from), Duration::hours(47));
+    assert_eq!(breakdown(from, to), days_hours_minutes(2, 0, 0));
+
+    // from 12:30 am to 3:30 am local time, 2 hours apart
+    assert_eq!(
+        breakdown(utc(3, 31, 0, 30), utc(3, 31, 2, 30)),
+        days_hours_minutes(0, 2, 0)
+    );
+
+    // a day on from 1:30 am on Saturday is skipped over, so the day ends just after the
+    // transition
+    assert_eq!(
+        breakdown(utc(3, 30, 1, 30), utc(3, 31, 2, 0)),
+        days_hours_minutes(1, 0, 30)
+    );
+}
+
+#[test]
+fn clocks_going_back() {
+    // 2 am BST became 1 am GMT on 27 October 2024
+    // from noon on Saturday to noon on Monday, 49 hours apart
+    let (from, to) = (utc(10, 26, 11, 0), utc(10, 28, 12, 0));
+    assert_eq!(Countdown::new(to).read_at(from), Duration::hours(49));
+    assert_eq!(breakdown(from, to), days_hours_minutes(2, 0, 0));
+
+    // from 3 am on Saturday to 2:30 am on Sunday local time, 24 and a half hours apart
+    assert_eq!(
+        breakdown(utc(10, 26, 2, 0), utc(10, 27, 2, 30)),
+        days_hours_minutes(1, 0, 30)
+    );
+
+    // from 1:30 am BST to 1:30 am GMT, an hour apart
+    assert_eq!(
+        breakdown(utc(10, 27, 0, 30), utc(10, 27, 1, 30)),
+        days_hours_minutes(0, 1, 0)
+    );
+}